@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+
+use crate::{board::components::Tile, point::Point};
+
+/// What is standing in the way of an [Action](super::Action).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Blocker {
+    Tile(Tile),
+    /// A [Piece](crate::pieces::components::Piece), with its `kind`.
+    Piece { entity: Entity, kind: String },
+}
+
+/// Why an [Action](super::Action) could not be executed. The [Display] text is meant
+/// for the player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionError {
+    Blocked(Blocker),
+    /// The target is off the [Board](crate::board::Board).
+    OutOfBounds(Point),
+    OutOfRange { distance: i32, range: i32 },
+    /// There is nothing at the target the action could apply to.
+    NoTarget(Point),
+    MissingComponent { entity: Entity, component: &'static str },
+    /// A Bevy [Resource] the action needs does not exist.
+    MissingResource(&'static str),
+    /// The actor doesn't have enough of something the action uses up.
+    #[allow(dead_code)]
+    InsufficientResource { resource: &'static str, needed: u32, available: u32 },
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+impl ActionError {
+    pub fn missing_component<T: Component>(entity: Entity) -> Self {
+        ActionError::MissingComponent { entity, component: short_type_name::<T>() }
+    }
+
+    pub fn missing_resource<T: Resource>() -> Self {
+        ActionError::MissingResource(short_type_name::<T>())
+    }
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionError::Blocked(Blocker::Tile(Tile::Wall)) => write!(f, "There's a wall in the way."),
+            ActionError::Blocked(Blocker::Tile(Tile::DoorClosed)) => write!(f, "The door is closed."),
+            ActionError::Blocked(Blocker::Tile(tile)) => write!(f, "The {tile:?} is in the way."),
+            ActionError::Blocked(Blocker::Piece { kind, .. }) => write!(f, "The {kind} is in the way."),
+            ActionError::OutOfBounds(_) => write!(f, "You can't go there."),
+            ActionError::OutOfRange { .. } => write!(f, "That's too far away."),
+            ActionError::NoTarget(_) => write!(f, "There's nothing there."),
+            ActionError::MissingComponent { component, .. } => write!(f, "You can't do that (no {component})."),
+            ActionError::MissingResource(resource) => write!(f, "You can't do that right now (no {resource})."),
+            ActionError::InsufficientResource { resource, needed, available } => {
+                write!(f, "Not enough {resource} ({available} of {needed}).")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
@@ -2,11 +2,16 @@ use std::{collections::VecDeque, fmt::Debug};
 
 use bevy::prelude::*;
 
-use crate::state::GameState;
+use crate::config;
 
 pub(crate) mod models;
+mod error;
+mod schedule;
 mod systems;
 
+pub use error::{ActionError, Blocker};
+pub use schedule::GameTime;
+
 /// A type implementing [Action] is a request of some kind as a separate
 /// object (see the Command pattern). An [Action] can be constructed beforehand
 /// and stored in a queue or another data structure (to wait for an animation 
@@ -15,9 +20,16 @@ mod systems;
 /// All actions provide an `execute` method that allows for applying it to the
 /// game world (e.g. move a character, interact with other game objects, and so on).
 ///
-/// Actions can generate other further actions.
+/// Actions can generate other further actions. An action that can't be executed
+/// returns an [ActionError] saying why.
 pub trait Action: Send + Sync + Debug {
-    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ()>;
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError>;
+
+    /// [Energy](crate::pieces::components::Energy) an actor spends on this action.
+    /// Only the chosen action of an actor costs energy, not the ones it generates.
+    fn cost(&self) -> u32 {
+        config::time::ACTION_COST
+    }
 }
 
 pub struct ActionsPlugin;
@@ -25,6 +37,7 @@ pub struct ActionsPlugin;
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ActorQueue>()
+            .init_resource::<GameTime>()
             .init_resource::<PendingActions>()
             .add_event::<TickEvent>()
             .add_event::<NextActorEvent>()
@@ -38,10 +51,6 @@ impl Plugin for ActionsPlugin {
                     .run_if(on_event::<TickEvent>())
                     .in_set(ActionSet::PostPlanning)
             )
-            .add_systems(
-                OnExit(GameState::PlayerInput), 
-                systems::populate_actor_queue
-            )
             .add_systems(
                 Update, 
                 (systems::plan_walk, systems::plan_melee)
@@ -56,7 +65,9 @@ enum ActionSet {
     PostPlanning,
 }
 
-/// Bevy [Resource] holding a queue of [Entity]s that want to perform [Action]s.
+/// Bevy [Resource] holding the [Entity] whose turn it is. The player's input puts
+/// the player in here, everyone else is queued by the energy scheduler (see
+/// [Speed](crate::pieces::components::Speed)) once the previous actor is done.
 #[derive(Resource, Default)]
 pub struct ActorQueue(pub VecDeque<Entity>);
 
@@ -91,8 +102,8 @@ pub struct NextActorEvent;
 pub struct ActionsCompleteEvent;
 
 /// Sent by [systems::process_action_queue] whenever an [Action] executed by the player
-/// returned `false`, i.e. the action was invalid.
+/// failed, with the reason it failed.
 #[derive(Event)]
-pub struct InvalidPlayerActionEvent;
+pub struct InvalidPlayerActionEvent(pub ActionError);
 
 
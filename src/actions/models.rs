@@ -1,20 +1,54 @@
 use std::fmt::Debug;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, ecs::query::Has};
 
-use crate::{point::Point, board::{components::Position, Board}, pieces::components::{TileOccupier, Health}};
-use super::Action;
+use crate::{combat::{self, AttackOutcome}, messages::{self, MessageKind, MessageLog}, player::Player, random::Rngs, point::Point, board::{components::{Position, Tile}, Board}, pieces::{components::{Actor, TileOccupier, Health, Piece, Fighter, Faction}, death::{Dead, DeathEvent}, status::{Inflicts, StatusEffect, StatusEffects}}};
+use super::{Action, ActionError, Blocker, GameTime};
 
 
 /// When executed, attempts to move the [Entity] to the specified [Point]. The [Action] 
-/// is invalid if there is no [CurrentBoard], the [Point] is not a valid coordinate for
-/// the [CurrentBoard], or if the [Entity] does not have a [Position] coordinate.
+/// is invalid if there is no [Board], the [Point] is not a walkable coordinate on
+/// the [Board], or if the [Entity] does not have a [Position] coordinate.
 #[derive(Debug)]
 pub struct MoveToAction {
     pub entity: Entity,
     pub destination: Point,
 }
 
+/// The message about an attack, from the player's point of view.
+fn attack_message(world: &World, attacker: Entity, target: Entity, outcome: AttackOutcome) -> (MessageKind, String) {
+    let player_attacks = world.get::<Player>(attacker).is_some();
+    let kind = match (player_attacks, world.get::<Player>(target).is_some()) {
+        (true, _) => MessageKind::Attack,
+        (false, true) => MessageKind::Hurt,
+        (false, false) => MessageKind::Info,
+    };
+
+    let subject = messages::capitalize(&messages::name_of(world, attacker));
+    // "you hit" but "the goblin hits"
+    let s = if player_attacks { "" } else { "s" };
+    let target = messages::name_of(world, target);
+    let text = match outcome {
+        AttackOutcome::Fumble => format!("{subject} fumble{s} the attack on {target}."),
+        AttackOutcome::Miss => format!("{subject} miss{} {target}.", if player_attacks { "" } else { "es" }),
+        AttackOutcome::Hit { damage, absorbed: 0 } => format!("{subject} hit{s} {target} for {damage}."),
+        AttackOutcome::Hit { damage, absorbed } => format!("{subject} hit{s} {target} for {damage} ({absorbed} absorbed)."),
+        AttackOutcome::Critical { damage, .. } => format!("{subject} critically hit{s} {target} for {damage}!"),
+    };
+    (kind, text)
+}
+
+/// The [TileOccupier] standing at `p`, if any.
+fn occupier_at(world: &mut World, p: Point) -> Option<Blocker> {
+    world.query_filtered::<(Entity, &Position, Option<&Piece>), With<TileOccupier>>()
+        .iter(world)
+        .find(|(_, pos, _)| pos.p == p)
+        .map(|(entity, _, piece)| Blocker::Piece {
+            entity,
+            kind: piece.map(|p| p.kind.clone()).unwrap_or_default(),
+        })
+}
+
 impl MoveToAction {
     pub fn new(entity: Entity, destination: Point) -> Self {
         Self { entity, destination }
@@ -22,16 +56,21 @@ impl MoveToAction {
 }
 
 impl Action for MoveToAction {
-    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ()> {
-        let Some(board) = world.get_resource::<Board>() else { return Err(()) };
-        if !board.in_bounds_xy(self.destination.x, self.destination.y) { return Err(()) };
-        
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(board) = world.get_resource::<Board>() else { return Err(ActionError::missing_resource::<Board>()) };
+        let p = self.destination;
+        if !board.in_bounds_xy(p.x, p.y) { return Err(ActionError::OutOfBounds(p)) };
+        let tile = board.get_tile_xy(p.x as u32, p.y as u32);
+        if !tile.is_walkable() { return Err(ActionError::Blocked(Blocker::Tile(tile))) };
+
         // If there are any entities at the target destination that already occupy that tile,
         // the action is not possible.
-        if world.query_filtered::<&Position, With<TileOccupier>>().iter(world).any(|pos| pos.p == self.destination) {
-            return Err(());
+        if let Some(blocker) = occupier_at(world, p) {
+            return Err(ActionError::Blocked(blocker));
         }
-        let Some(mut pos) = world.get_mut::<Position>(self.entity) else { return Err(()) };
+        let Some(mut pos) = world.get_mut::<Position>(self.entity) else {
+            return Err(ActionError::missing_component::<Position>(self.entity))
+        };
         pos.p = self.destination;
         Ok(Vec::new())
     }
@@ -39,61 +78,303 @@ impl Action for MoveToAction {
 
 /// Uses a [Point] for the target of the attack rather than an [Entity]. This makes for
 /// a more costly position lookup, but in the long run will give more flexibility.
+/// Each target is attacked separately, see [combat::resolve_attack].
 #[derive(Debug)]
 pub struct MeleeAttackAction {
     pub attacker: Entity,
     pub target_pos: Point,
-    pub damage: u32,
 }
 
 impl Action for MeleeAttackAction {
-   fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ()> {
+   fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
         let Some(attacker_pos) = world.get::<Position>(self.attacker) else { 
-            warn!("Position component not found for entity {:?}", self.attacker);
-            return Err(()) 
+            return Err(ActionError::missing_component::<Position>(self.attacker))
         };
 
         // Melee means only adjacent tiles are in range
         let dist = attacker_pos.p.dist_chebyshev(self.target_pos);
-        if dist > 1 { return Err(()) };
+        if dist > 1 { return Err(ActionError::OutOfRange { distance: dist, range: 1 }) };
 
         // Valid targets are any entities with a health component at the target position
-        let target_entities = world.query_filtered::<(Entity, &Position), With<Health>>()
+        let target_entities = world.query_filtered::<(Entity, &Position), (With<Health>, Without<Dead>)>()
             .iter(world)
             .filter(|(_, pos)| pos.p == self.target_pos)
+            .map(|(entity, _)| entity)
             .collect::<Vec<_>>();
         if target_entities.len() == 0 { 
-            warn!("No valid entities to attack at location {:?}", self.target_pos);
-            return Err(()); 
+            return Err(ActionError::NoTarget(self.target_pos)); 
         }
 
-        let result = target_entities.iter()
-            .map(|e| Box::new(DamageAction::new(e.0, self.damage)) as Box<dyn Action>)
-            .collect::<Vec<_>>();
+        let Some(attacker) = world.get::<Fighter>(self.attacker).cloned() else {
+            return Err(ActionError::missing_component::<Fighter>(self.attacker))
+        };
+        let turn = world.get_resource::<GameTime>().map_or(0, |t| t.turns);
+        let Some(mut rngs) = world.get_resource_mut::<Rngs>() else { return Err(ActionError::missing_resource::<Rngs>()) };
+        let mut rng = combat::attack_rng(&mut rngs, turn);
+        let inflicts = world.get::<Inflicts>(self.attacker).cloned().unwrap_or_default();
+
+        let mut result = Vec::new();
+        for target in target_entities {
+            let outcome = combat::resolve_attack(&attacker, world.get::<Fighter>(target), &mut rng);
+            let (kind, text) = attack_message(world, self.attacker, target, outcome);
+            MessageLog::push_to(world, kind, text);
+            if outcome.damage() > 0 {
+                result.push(Box::new(DamageAction::new(target, outcome.damage(), Some(self.attacker))) as Box<dyn Action>);
+                for inflict in inflicts.0.iter().filter(|i| rng.gen_bool(i.chance.clamp(0., 1.))) {
+                    let effect = StatusEffect {
+                        kind: inflict.status,
+                        turns: inflict.turns,
+                        potency: inflict.potency,
+                        source: Some(self.attacker),
+                    };
+                    result.push(Box::new(ApplyStatusAction::new(target, effect)));
+                }
+            }
+        }
 
         Ok(result)
     } 
 }
 
+/// Lowers the [Health] of the [Entity]. At zero health it is [Dead]: it stops acting
+/// and blocking, and a [DeathEvent] crediting `source` is sent.
 #[derive(Debug)]
 pub struct DamageAction {
     pub entity: Entity,
     pub value: u32,
+    pub source: Option<Entity>,
 }
 
 impl DamageAction {
-    pub fn new(entity: Entity, value: u32) -> Self {
-        Self { entity, value }
+    pub fn new(entity: Entity, value: u32, source: Option<Entity>) -> Self {
+        Self { entity, value, source }
     }
 }
 
 impl Action for DamageAction {
-    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ()> {
-        let Some(mut health) = world.get_mut::<Health>(self.entity) else { return Err(()) };
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(mut health) = world.get_mut::<Health>(self.entity) else {
+            return Err(ActionError::missing_component::<Health>(self.entity))
+        };
         health.value = health.value.saturating_sub(self.value);
-        if health.value == 0 {
-            world.despawn(self.entity);
+        if health.value == 0 && world.get::<Dead>(self.entity).is_none() {
+            world.entity_mut(self.entity).remove::<(Actor, TileOccupier)>().insert(Dead);
+            world.send_event(DeathEvent { entity: self.entity, killer: self.source });
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Raises the [Health] of the [Entity], up to its maximum.
+#[derive(Debug)]
+pub struct HealAction {
+    pub entity: Entity,
+    pub value: u32,
+}
+
+impl HealAction {
+    pub fn new(entity: Entity, value: u32) -> Self {
+        Self { entity, value }
+    }
+}
+
+impl Action for HealAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(mut health) = world.get_mut::<Health>(self.entity) else {
+            return Err(ActionError::missing_component::<Health>(self.entity))
+        };
+        // Only touch the component if it changes, so the HUD isn't redrawn every turn.
+        let value = (health.value + self.value).min(health.max);
+        if value != health.value {
+            health.value = value;
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Gives the [Entity] a status effect, stacking it with the ones it already has (see
+/// [StatusEffects::apply]). Pieces that just died are left alone.
+#[derive(Debug)]
+pub struct ApplyStatusAction {
+    pub entity: Entity,
+    pub effect: StatusEffect,
+}
+
+impl ApplyStatusAction {
+    pub fn new(entity: Entity, effect: StatusEffect) -> Self {
+        Self { entity, effect }
+    }
+}
+
+impl Action for ApplyStatusAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        if world.get::<Dead>(self.entity).is_some() { return Ok(Vec::new()) };
+        let Some(mut effects) = world.get_mut::<StatusEffects>(self.entity) else {
+            return Err(ActionError::missing_component::<StatusEffects>(self.entity))
+        };
+        effects.apply(self.effect.clone());
+
+        let is_player = world.get::<Player>(self.entity).is_some();
+        let name = messages::capitalize(&messages::name_of(world, self.entity));
+        let (kind, verb) = if is_player { (MessageKind::Hurt, "are") } else { (MessageKind::Info, "is") };
+        MessageLog::push_to(world, kind, format!("{name} {verb} {}!", self.effect.kind.adjective()));
+        Ok(Vec::new())
+    }
+}
+
+/// Opens a closed door at `target`. The door must be adjacent to the [Entity].
+#[derive(Debug)]
+pub struct OpenDoorAction {
+    pub entity: Entity,
+    pub target: Point,
+}
+
+impl OpenDoorAction {
+    pub fn new(entity: Entity, target: Point) -> Self {
+        Self { entity, target }
+    }
+}
+
+impl Action for OpenDoorAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(pos) = world.get::<Position>(self.entity) else {
+            return Err(ActionError::missing_component::<Position>(self.entity))
+        };
+        let distance = pos.p.dist_chebyshev(self.target);
+        if distance > 1 { return Err(ActionError::OutOfRange { distance, range: 1 }) };
+
+        let Some(mut board) = world.get_resource_mut::<Board>() else { return Err(ActionError::missing_resource::<Board>()) };
+        if !board.in_bounds_xy(self.target.x, self.target.y) { return Err(ActionError::OutOfBounds(self.target)) };
+        let (x, y) = (self.target.x as u32, self.target.y as u32);
+        if board.get_tile_xy(x, y) != Tile::DoorClosed { return Err(ActionError::NoTarget(self.target)) };
+
+        board.set_tile_xy(x, y, Tile::DoorOpen);
+        Ok(Vec::new())
+    }
+}
+
+/// Closes an open door at `target`. The door must be adjacent to the [Entity] and
+/// nothing may be standing in the doorway.
+#[derive(Debug)]
+pub struct CloseDoorAction {
+    pub entity: Entity,
+    pub target: Point,
+}
+
+impl CloseDoorAction {
+    #[allow(dead_code)]
+    pub fn new(entity: Entity, target: Point) -> Self {
+        Self { entity, target }
+    }
+}
+
+impl Action for CloseDoorAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(pos) = world.get::<Position>(self.entity) else {
+            return Err(ActionError::missing_component::<Position>(self.entity))
+        };
+        let distance = pos.p.dist_chebyshev(self.target);
+        if distance > 1 { return Err(ActionError::OutOfRange { distance, range: 1 }) };
+
+        if let Some(blocker) = occupier_at(world, self.target) {
+            return Err(ActionError::Blocked(blocker));
         }
+
+        let Some(mut board) = world.get_resource_mut::<Board>() else { return Err(ActionError::missing_resource::<Board>()) };
+        if !board.in_bounds_xy(self.target.x, self.target.y) { return Err(ActionError::OutOfBounds(self.target)) };
+        let (x, y) = (self.target.x as u32, self.target.y as u32);
+        if board.get_tile_xy(x, y) != Tile::DoorOpen { return Err(ActionError::NoTarget(self.target)) };
+
+        board.set_tile_xy(x, y, Tile::DoorClosed);
         Ok(Vec::new())
     }
 }
+
+/// Makes two adjacent pieces trade places, e.g. the player and an ally in a corridor.
+#[derive(Debug)]
+pub struct SwapPlacesAction {
+    pub entity: Entity,
+    pub other: Entity,
+}
+
+impl SwapPlacesAction {
+    pub fn new(entity: Entity, other: Entity) -> Self {
+        Self { entity, other }
+    }
+}
+
+impl Action for SwapPlacesAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let Some(a) = world.get::<Position>(self.entity).map(|pos| pos.p) else {
+            return Err(ActionError::missing_component::<Position>(self.entity))
+        };
+        let Some(b) = world.get::<Position>(self.other).map(|pos| pos.p) else {
+            return Err(ActionError::missing_component::<Position>(self.other))
+        };
+        let distance = a.dist_chebyshev(b);
+        if distance > 1 { return Err(ActionError::OutOfRange { distance, range: 1 }) };
+
+        // Both positions were just read, so the components are there.
+        world.get_mut::<Position>(self.entity).unwrap().p = b;
+        world.get_mut::<Position>(self.other).unwrap().p = a;
+        Ok(Vec::new())
+    }
+}
+
+/// The player's command to go in a `direction`, resolved by what is there (see
+/// [BumpAction::resolve]) when it's executed. Fails like the resolved action would,
+/// e.g. with [Blocked](ActionError::Blocked) when walking into a wall.
+#[derive(Debug)]
+pub struct BumpAction {
+    pub entity: Entity,
+    pub direction: Point,
+}
+
+impl BumpAction {
+    pub fn new(entity: Entity, direction: Point) -> Self {
+        Self { entity, direction }
+    }
+
+    /// Picks the action for the target tile:
+    ///
+    /// * a [MeleeAttackAction] on a piece of another [Faction] with [Health],
+    /// * a [SwapPlacesAction] with a piece of the same faction,
+    /// * an [OpenDoorAction] on a closed door,
+    /// * a [MoveToAction] otherwise.
+    pub fn resolve(&self, world: &mut World) -> Result<Box<dyn Action>, ActionError> {
+        let Some(pos) = world.get::<Position>(self.entity) else {
+            return Err(ActionError::missing_component::<Position>(self.entity))
+        };
+        let target = pos.p + self.direction;
+        let faction = world.get::<Faction>(self.entity).copied();
+
+        let occupier = world.query_filtered::<(Entity, &Position, Option<&Faction>, Has<Health>), With<TileOccupier>>()
+            .iter(world)
+            .find(|(entity, pos, _, _)| pos.p == target && *entity != self.entity)
+            .map(|(entity, _, other_faction, has_health)| (entity, other_faction.copied(), has_health));
+
+        if let Some((other, other_faction, has_health)) = occupier {
+            if faction.is_some() && other_faction == faction {
+                return Ok(Box::new(SwapPlacesAction::new(self.entity, other)));
+            }
+            if has_health {
+                return Ok(Box::new(MeleeAttackAction { attacker: self.entity, target_pos: target }));
+            }
+            // Anything else in the way is reported by the move.
+        }
+
+        let Some(board) = world.get_resource::<Board>() else { return Err(ActionError::missing_resource::<Board>()) };
+        if board.in_bounds_xy(target.x, target.y) && board.get_tile_xy(target.x as u32, target.y as u32) == Tile::DoorClosed {
+            return Ok(Box::new(OpenDoorAction::new(self.entity, target)));
+        }
+        Ok(Box::new(MoveToAction::new(self.entity, target)))
+    }
+}
+
+impl Action for BumpAction {
+    fn execute(&self, world: &mut World) -> Result<Vec<Box<dyn Action>>, ActionError> {
+        let action = self.resolve(world)?;
+        debug!(entity = ?self.entity, ?action, "bump resolved");
+        action.execute(world)
+    }
+}
@@ -0,0 +1,159 @@
+use std::cmp::Reverse;
+
+use bevy::{prelude::*, ecs::query::Has};
+use serde::{Deserialize, Serialize};
+
+use crate::{pieces::{components::{Actor, Energy, Speed}, status::StatusEffects}, player::Player, config::time::READY_ENERGY};
+
+use super::ActorQueue;
+
+/// Bevy [Resource] counting game time, for things that depend on when they happen
+/// (e.g. the [attack rolls](crate::combat::attack_rng)). Saved along with the [Rngs]
+/// (see [saveload](crate::saveload)).
+///
+/// [Rngs]: crate::random::Rngs
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameTime {
+    /// Ticks of game time, see [next_ready_actor].
+    pub ticks: u64,
+    /// Turns taken by all actors together.
+    pub turns: u64,
+}
+
+/// Number of ticks until an actor has enough energy to act, `None` if it never will.
+fn ticks_until_ready(energy: i32, speed: u32) -> Option<i32> {
+    if energy >= READY_ENERGY {
+        return Some(0);
+    }
+    (speed > 0).then(|| (READY_ENERGY - energy + speed as i32 - 1) / speed as i32)
+}
+
+/// Spends `cost` of the actor's [Energy] at the end of its turn.
+pub fn end_turn(world: &mut World, entity: Entity, cost: u32) {
+    if let Some(mut energy) = world.get_mut::<Energy>(entity) {
+        energy.0 -= cost as i32;
+    }
+    world.resource_mut::<GameTime>().turns += 1;
+}
+
+/// Advances game time until an [Actor] is ready and returns it. Every actor gains
+/// its [Speed] (changed by its [StatusEffects]) in [Energy] per tick; the time in
+/// between is skipped in one go.
+///
+/// If several actors are ready the one with the most energy goes first, then everyone
+/// before the [Player] (who has just acted), then the lowest [Entity] in Bevy's
+/// ordering (by index, which may have been reused, so not necessarily the oldest).
+/// There's no randomness, so the order only depends on the game state.
+fn next_ready_actor(world: &mut World) -> Option<Entity> {
+    let mut query = world.query_filtered::<(Entity, &mut Energy, &Speed, Option<&StatusEffects>, Has<Player>), With<Actor>>();
+    let mut actors = query.iter_mut(world)
+        .map(|(entity, energy, speed, effects, is_player)| {
+            let speed = effects.map_or(speed.0, |e| e.speed(speed.0));
+            (entity, energy, speed, is_player)
+        })
+        .collect::<Vec<_>>();
+
+    let ticks = actors.iter()
+        .filter_map(|(_, energy, speed, _)| ticks_until_ready(energy.0, *speed))
+        .min()?;
+    if ticks > 0 {
+        for (_, energy, speed, _) in actors.iter_mut() {
+            energy.0 += ticks * *speed as i32;
+        }
+    }
+
+    let next = actors.iter()
+        .filter(|(_, energy, _, _)| energy.0 >= READY_ENERGY)
+        .max_by_key(|(entity, energy, _, is_player)| (energy.0, !is_player, Reverse(*entity)))
+        .map(|(entity, _, _, _)| *entity);
+    world.resource_mut::<GameTime>().ticks += ticks as u64;
+    next
+}
+
+/// Puts the next actor to move in the [ActorQueue], unless it's the player's turn
+/// (the player is queued by the input instead) or the queue isn't empty yet.
+pub fn queue_next_actor(world: &mut World) {
+    if !world.resource::<ActorQueue>().0.is_empty() { return; }
+
+    let Some(entity) = next_ready_actor(world) else { return };
+    if world.get::<Player>(entity).is_some() { return; }
+    world.resource_mut::<ActorQueue>().0.push_back(entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::time::NORMAL_SPEED, pieces::status::{StatusEffect, StatusKind}};
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<GameTime>();
+        world.init_resource::<ActorQueue>();
+        world
+    }
+
+    fn spawn(world: &mut World, speed: u32, energy: i32) -> Entity {
+        world.spawn((Actor::default(), Speed(speed), Energy(energy))).id()
+    }
+
+    /// The actors in the order they act, each spending a normal turn.
+    fn turns(world: &mut World, count: usize) -> Vec<Entity> {
+        (0..count)
+            .map(|_| {
+                let entity = next_ready_actor(world).unwrap();
+                end_turn(world, entity, READY_ENERGY as u32);
+                entity
+            })
+            .collect()
+    }
+
+    #[test]
+    fn speeds_interleave() {
+        let mut world = world();
+        let slow = spawn(&mut world, NORMAL_SPEED / 2, 0);
+        let player = spawn(&mut world, NORMAL_SPEED, 0);
+        world.entity_mut(player).insert(Player);
+        let fast = spawn(&mut world, NORMAL_SPEED * 2, 0);
+
+        let order = turns(&mut world, 14);
+        assert_eq!(order, vec![
+            fast, fast, player, fast, slow, fast, player,
+            fast, fast, player, fast, slow, fast, player,
+        ]);
+        assert_eq!(world.resource::<GameTime>().turns, 14);
+        assert_eq!(world.resource::<GameTime>().ticks, 4);
+    }
+
+    #[test]
+    fn ties() {
+        let mut world = world();
+        let player = spawn(&mut world, NORMAL_SPEED, 0);
+        world.entity_mut(player).insert(Player);
+        let first = spawn(&mut world, NORMAL_SPEED, 0);
+        let second = spawn(&mut world, NORMAL_SPEED, 0);
+
+        // Everyone is ready at once: monsters before the player, lowest entity first.
+        assert_eq!(turns(&mut world, 3), vec![first, second, player]);
+
+        // Unless someone has more energy left.
+        world.get_mut::<Energy>(player).unwrap().0 += 10;
+        assert_eq!(turns(&mut world, 3), vec![player, first, second]);
+    }
+
+    #[test]
+    fn status_changes_speed() {
+        let mut world = world();
+        let hasted = spawn(&mut world, NORMAL_SPEED, 0);
+        let normal = spawn(&mut world, NORMAL_SPEED, 0);
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect {
+            kind: StatusKind::Haste,
+            turns: 10,
+            potency: 1,
+            source: None,
+        });
+        world.entity_mut(hasted).insert(effects);
+
+        assert_eq!(turns(&mut world, 6), vec![hasted, hasted, normal, hasted, hasted, normal]);
+    }
+}
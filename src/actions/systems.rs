@@ -1,32 +1,25 @@
 use bevy::prelude::*;
 
-use crate::{pieces::components::{Actor, Walker, TileOccupier, Fighter}, player::Player, board::{components::Position, Board}, point::Point, pathfind};
+use crate::{pieces::{components::{Actor, Walker, TileOccupier, Fighter}, status::{self, StatusEffects, StatusKind}}, player::Player, board::{components::Position, Board}, point::Point, pathfind, config, messages::{self, MessageKind, MessageLog}, random::{RngStream, Rngs}};
 
-use super::{ActorQueue, models::{MoveToAction, MeleeAttackAction}, InvalidPlayerActionEvent, NextActorEvent, PendingActions};
+use super::{ActorQueue, models::{MoveToAction, MeleeAttackAction}, schedule, InvalidPlayerActionEvent, NextActorEvent, PendingActions};
 
 pub const MOVE_SCORE: i32 = 50;
 pub const PLAYER_ATTACK_SCORE: i32 = 100;
 
-/// This system assumes the action queue has already been populated with the 
-/// player entity from the player input system.
-///
-/// Currently any [Entity] with an [Actor] component that is not the [Player]
-/// is added to the queue each time this is called (which should be once 
-/// whenever we leave the [PlayerInput](crate::state::GameState::PlayerInput) 
-/// state---so, once per turn.
-pub fn populate_actor_queue(
-    query: Query<Entity, (With<Actor>, Without<Player>)>,
-    mut queue: ResMut<ActorQueue>,
-) {
-    queue.0.extend(query.iter());
-}
-
 /// Attempts to perform an [Action](crate::actions::Action) of the [Entity] in the
-/// front of the [ActorQueue]. On successful execution of the action, this system 
-/// emits a [NextActorEvent](super::NextActorEvent) to trigger planning of actions
-/// for the next entity in the queue. This is because each action undertaken
-/// by an entity may affect the board state in ways that must be taken into account
-/// when planning further actions.
+/// front of the [ActorQueue], which pays the action's cost in energy. A stunned
+/// actor loses its turn instead. At the end of the turn its status effects tick
+/// (see [status::tick]), which may add pending actions. It then queues
+/// whoever is ready next (see [schedule::queue_next_actor]) and emits a
+/// [NextActorEvent](super::NextActorEvent) to trigger planning of actions for them.
+/// This is because each action undertaken by an entity may affect the board state
+/// in ways that must be taken into account when planning further actions.
+///
+/// This system assumes the action queue has already been populated with the 
+/// player entity from the player input system. Once it's the player's turn again
+/// the queue stays empty and an [ActionsCompleteEvent](super::ActionsCompleteEvent)
+/// is sent.
 pub fn process_action_queue(world: &mut World) {
 
     if process_pending_actions(world) { return };
@@ -41,39 +34,67 @@ pub fn process_action_queue(world: &mut World) {
         return;
     };
 
-    let Some(mut actor) = world.get_mut::<Actor>(entity) else {
-        warn!(
-            "Entity {:?} found in action queue ({}) but has no Actor component: {}", 
-            entity, 
-            std::any::type_name::<ActorQueue>(),
-            std::any::type_name::<Actor>(),
-        );
+    // It died (see DamageAction) after it was queued: move on to whoever is next,
+    // or the schedule would stall.
+    if world.get::<Actor>(entity).is_none() {
+        debug!(?entity, "queued entity has no {}, skipping it", std::any::type_name::<Actor>());
+        schedule::queue_next_actor(world);
+        world.send_event(NextActorEvent);
         return;
-    };
+    }
+
+    let stunned = world.get::<StatusEffects>(entity).is_some_and(|s| s.has(StatusKind::Stun));
+    if stunned {
+        let name = messages::capitalize(&messages::name_of(world, entity));
+        let verb = if world.get::<Player>(entity).is_some() { "are" } else { "is" };
+        MessageLog::push_to(world, MessageKind::Info, format!("{name} {verb} stunned and can't act."));
+    }
+
+    // Checked above.
+    let mut actor = world.get_mut::<Actor>(entity).unwrap();
 
     // clear the Vec of actions and sort it with highest score first
     let mut possible_actions = actor.0.drain(..).collect::<Vec<_>>();
     possible_actions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    if stunned {
+        possible_actions.clear();
+    }
 
-    let mut success = false;
+    let mut error = None;
+    // An actor that can't do anything waits, which takes as long as an ordinary action.
+    let mut cost = config::time::ACTION_COST;
     for action in possible_actions {
-        if let Ok(result) = action.0.execute(world) {
-            if let Some(mut pending) = world.get_resource_mut::<PendingActions>() {
-                pending.0 = result;
-            }
-            success = true;
-            break;
-        } else {
-            println!("Action {:?} for entity {:?} failed", action, entity);
+        match action.0.execute(world) {
+            Ok(result) => {
+                if let Some(mut pending) = world.get_resource_mut::<PendingActions>() {
+                    pending.0 = result;
+                }
+                cost = action.0.cost();
+                error = None;
+                break;
+            },
+            Err(e) => {
+                debug!(?entity, action = ?action.0, error = ?e, "action failed");
+                error = Some(e);
+            },
         }
     }
 
-    if !success && world.get::<Player>(entity).is_some() {
-        debug!("Invalid Player action");
-        world.send_event(InvalidPlayerActionEvent);
-        return;
+    if let Some(e) = error {
+        if world.get::<Player>(entity).is_some() {
+            debug!(?entity, error = ?e, "invalid player action");
+            world.send_event(InvalidPlayerActionEvent(e));
+            return;
+        }
+    }
+
+    let follow_ups = status::tick(world, entity);
+    if let Some(mut pending) = world.get_resource_mut::<PendingActions>() {
+        pending.0.extend(follow_ups);
     }
 
+    schedule::end_turn(world, entity, cost);
+    schedule::queue_next_actor(world);
     world.send_event(NextActorEvent);
 }
 
@@ -88,9 +109,12 @@ fn process_pending_actions(world: &mut World) -> bool {
     let mut next = Vec::new();
     let mut success = false;
     for action in pending {
-        if let Ok(result) = action.execute(world) {
-            next.extend(result);
-            success = true;
+        match action.execute(world) {
+            Ok(result) => {
+                next.extend(result);
+                success = true;
+            },
+            Err(e) => debug!(action = ?action, error = ?e, "pending action failed"),
         }
     }
 
@@ -102,19 +126,26 @@ fn process_pending_actions(world: &mut World) -> bool {
 }
 
 /// Temporary system that creates a MoveToAction in a random direction.
+/// [Confused](StatusKind::Confusion) walkers stumble into a random direction.
 pub fn plan_walk(
-    mut query: Query<(&Position, &mut Actor), With<Walker>>,
+    mut query: Query<(&Position, &mut Actor, Option<&StatusEffects>), With<Walker>>,
     queue: Res<ActorQueue>,
     player_query: Query<&Position, With<Player>>,
     occupier_query: Query<&Position, With<TileOccupier>>,
     board: Res<Board>,
+    mut rngs: ResMut<Rngs>,
 ) {
     let Some(entity) = queue.0.get(0) else { return };
-    let Ok((pos, mut actor)) = query.get_mut(*entity) else { 
+    let Ok((pos, mut actor, effects)) = query.get_mut(*entity) else { 
         // Entity in queue isnt an Actor and a
         // Walker with a Position
         return
     };
+    if effects.is_some_and(|e| e.has(StatusKind::Confusion)) {
+        let dir = *rngs.stream(RngStream::Ai).choose(&Point::OCTANT).unwrap();
+        actor.0.push((Box::new(MoveToAction::new(*entity, pos.p + dir)), MOVE_SCORE));
+        return;
+    }
     let Ok(player_position) = player_query.get_single() else {
         warn!("Player has no Position component");
         return
@@ -125,7 +156,7 @@ pub fn plan_walk(
     let path_to_player = pathfind::path_astar(
         pos.p, 
         player_position.p, 
-        &board.iter_points().collect(), 
+        &board.iter_points().filter(|p| board.is_walkable(*p)).collect(), 
         &occupier_query.iter().map(|pos| pos.p).collect()
     );
 
@@ -145,8 +176,9 @@ pub fn plan_walk(
     actor.0.extend(actions);
 }
 
+/// Attacks the player. [Confused](StatusKind::Confusion) actors don't attack.
 pub fn plan_melee(
-    mut query: Query<(&mut Actor, &Fighter)>,
+    mut query: Query<(&mut Actor, &Fighter, Option<&StatusEffects>)>,
     player_query: Query<&Position, With<Player>>,
     queue: Res<ActorQueue>,
 ) {
@@ -154,12 +186,40 @@ pub fn plan_melee(
         return
     };
 
-    let Ok((mut actor, fighter)) = query.get_mut(*entity) else { return };
+    let Ok((mut actor, fighter, effects)) = query.get_mut(*entity) else { return };
+    if effects.is_some_and(|e| e.has(StatusKind::Confusion)) { return; }
     let Ok(player_position) = player_query.get_single() else { return };
     let action = Box::new(MeleeAttackAction {
         attacker: *entity,
         target_pos: player_position.p,
-        damage: fighter.strength,
     });
     actor.0.push((action, PLAYER_ATTACK_SCORE + fighter.strength as i32));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{actions::{ActionsCompleteEvent, GameTime}, config::time::{NORMAL_SPEED, READY_ENERGY}, pieces::components::{Energy, Speed}};
+
+    #[test]
+    fn dead_actor_is_skipped() {
+        let mut world = World::new();
+        world.init_resource::<ActorQueue>();
+        world.init_resource::<PendingActions>();
+        world.init_resource::<GameTime>();
+        world.init_resource::<Events<NextActorEvent>>();
+        world.init_resource::<Events<ActionsCompleteEvent>>();
+        world.init_resource::<Events<InvalidPlayerActionEvent>>();
+
+        // Killed after it was queued, so it lost its Actor.
+        let dead = world.spawn((Speed(NORMAL_SPEED), Energy(READY_ENERGY))).id();
+        let next = world.spawn((Actor::default(), Speed(NORMAL_SPEED), Energy(0))).id();
+        world.resource_mut::<ActorQueue>().0.push_back(dead);
+
+        process_action_queue(&mut world);
+
+        assert_eq!(world.resource::<ActorQueue>().0, [next]);
+        assert_eq!(world.resource::<Events<NextActorEvent>>().len(), 1);
+        assert!(world.resource::<Events<ActionsCompleteEvent>>().is_empty());
+    }
+}
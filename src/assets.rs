@@ -22,7 +22,7 @@ pub fn check_asset_loading(
         asset_list.0.iter().map(|a| a.id())
     ) {
             LoadState::Loaded => {
-                next_state.set(MainState::Game);
+                next_state.set(MainState::Generating);
             },
             LoadState::Failed => {
                 error!("asset loading error");
@@ -13,5 +13,14 @@ pub struct Position {
 pub enum Tile {
     Floor,
     Wall,
+    DoorOpen,
+    DoorClosed,
+    DownStairs,
 }
 
+impl Tile {
+    /// Returns `true` if a piece can stand on this [Tile].
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, Tile::Floor | Tile::DoorOpen | Tile::DownStairs)
+    }
+}
@@ -121,6 +121,11 @@ impl Board {
         && y <= b.3 as i32
     }
 
+    /// Returns `true` if `p` is on the board and its [Tile] can be walked on.
+    pub fn is_walkable(&self, p: Point) -> bool {
+        self.in_bounds_xy(p.x, p.y) && self.get_tile_xy(p.x as u32, p.y as u32).is_walkable()
+    }
+
     pub fn in_bounds(&self, i: usize) -> bool {
         let (x, y) = self.index_to_xy(i);
         self.in_bounds_xy(x as i32, y as i32)
@@ -0,0 +1,138 @@
+//! Resolution of attacks. An attack rolls a d20:
+//!
+//! * a natural 1 is a fumble and always misses,
+//! * a natural 20 is a critical hit: it always hits and rolls the damage dice twice,
+//! * otherwise it hits if `d20 + attack >= 10 + defense`.
+//!
+//! A hit deals the attacker's [Fighter::damage] plus half its strength, minus the
+//! defender's armor. Every attack gets its own [PRng], seeded from
+//! [MELEE_ATTACK_SEED](crate::config::MELEE_ATTACK_SEED), the turn and a draw from the
+//! combat stream instead of the attacker, see [attack_rng].
+
+use crate::{pieces::components::Fighter, random::{PRng, RngStream, Rngs}};
+
+const HIT_TARGET: i32 = 10;
+const FUMBLE_ROLL: i32 = 1;
+const CRITICAL_ROLL: i32 = 20;
+
+/// How an attack went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackOutcome {
+    Fumble,
+    Miss,
+    /// `absorbed` is the part of the damage stopped by armor.
+    Hit { damage: u32, absorbed: u32 },
+    Critical { damage: u32, absorbed: u32 },
+}
+
+impl AttackOutcome {
+    pub fn damage(&self) -> u32 {
+        match self {
+            AttackOutcome::Hit { damage, .. } | AttackOutcome::Critical { damage, .. } => *damage,
+            AttackOutcome::Fumble | AttackOutcome::Miss => 0,
+        }
+    }
+}
+
+/// The [PRng] for one attack: a draw from the [combat stream](RngStream::Combat) mixed
+/// with the turn. Both are saved with the game (unlike e.g. [Entity] ids, which change
+/// when it's loaded), so a reloaded game gives the same rolls.
+pub fn attack_rng(rngs: &mut Rngs, turn: u64) -> PRng {
+    let draw = rngs.stream(RngStream::Combat).gen();
+    rngs.for_turn(RngStream::Combat, turn)
+        .write_u64(draw)
+        .build()
+}
+
+/// Rolls an attack of `attacker` on `defender`. A defender without a [Fighter]
+/// has no defense and no armor.
+pub fn resolve_attack(attacker: &Fighter, defender: Option<&Fighter>, rng: &mut PRng) -> AttackOutcome {
+    let (defense, armor) = defender.map_or((0, 0), |d| (d.defense, d.armor));
+
+    let roll = rng.gen_range(1..=20);
+    let critical = match roll {
+        FUMBLE_ROLL => return AttackOutcome::Fumble,
+        CRITICAL_ROLL => true,
+        _ if roll + attacker.attack < HIT_TARGET + defense => return AttackOutcome::Miss,
+        _ => false,
+    };
+
+    let mut raw = attacker.damage.roll(rng) + attacker.strength as i32 / 2;
+    if critical {
+        raw += attacker.damage.roll_dice(rng);
+    }
+    let raw = raw.max(0) as u32;
+    let damage = raw.saturating_sub(armor);
+    let absorbed = raw - damage;
+
+    if critical {
+        AttackOutcome::Critical { damage, absorbed }
+    } else {
+        AttackOutcome::Hit { damage, absorbed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Dice, PRngBuilder};
+
+    /// A [PRng] whose first d20 comes up `roll`.
+    fn rng_rolling(roll: i32) -> PRng {
+        (0..)
+            .map(|seed| PRngBuilder::new_seeded(seed).build())
+            .find(|rng| rng.clone().gen_range(1..=20) == roll)
+            .unwrap()
+    }
+
+    /// Deals `2d1+3`, i.e. always 5, plus half its strength.
+    fn fighter(strength: u32, attack: i32, defense: i32, armor: u32) -> Fighter {
+        Fighter { strength, attack, defense, armor, damage: Dice::new(2, 1, 3) }
+    }
+
+    #[test]
+    fn fumble_always_misses() {
+        let attacker = fighter(0, 100, 0, 0);
+        assert_eq!(resolve_attack(&attacker, None, &mut rng_rolling(FUMBLE_ROLL)), AttackOutcome::Fumble);
+    }
+
+    #[test]
+    fn critical_always_hits() {
+        let attacker = fighter(4, -100, 0, 0);
+        let defender = fighter(0, 0, 100, 1);
+        // 5 + 2 from strength + another 2 from rolling the dice again, minus the armor.
+        assert_eq!(
+            resolve_attack(&attacker, Some(&defender), &mut rng_rolling(CRITICAL_ROLL)),
+            AttackOutcome::Critical { damage: 8, absorbed: 1 },
+        );
+    }
+
+    #[test]
+    fn armor_absorbs() {
+        let attacker = fighter(4, 100, 0, 0);
+        let hit = |armor| resolve_attack(&attacker, Some(&fighter(0, 0, 0, armor)), &mut rng_rolling(10));
+
+        assert_eq!(hit(0), AttackOutcome::Hit { damage: 7, absorbed: 0 });
+        assert_eq!(hit(4), AttackOutcome::Hit { damage: 3, absorbed: 4 });
+        assert_eq!(hit(9), AttackOutcome::Hit { damage: 0, absorbed: 7 });
+        assert_eq!(hit(9).damage(), 0);
+    }
+
+    #[test]
+    fn hit_threshold() {
+        for roll in FUMBLE_ROLL + 1..CRITICAL_ROLL {
+            for defense in [-3, 0, 5] {
+                let defender = fighter(0, 0, defense, 0);
+                // Exactly enough to hit, then one short.
+                let attack = HIT_TARGET + defense - roll;
+                let hit = resolve_attack(&fighter(0, attack, 0, 0), Some(&defender), &mut rng_rolling(roll));
+                assert!(matches!(hit, AttackOutcome::Hit { .. }), "roll {roll} defense {defense}: {hit:?}");
+                let miss = resolve_attack(&fighter(0, attack - 1, 0, 0), Some(&defender), &mut rng_rolling(roll));
+                assert_eq!(miss, AttackOutcome::Miss, "roll {roll} defense {defense}");
+            }
+        }
+        // Without a defender there is no defense.
+        assert_eq!(resolve_attack(&fighter(0, 0, 0, 0), None, &mut rng_rolling(9)), AttackOutcome::Miss);
+        assert!(matches!(resolve_attack(&fighter(0, 0, 0, 0), None, &mut rng_rolling(10)), AttackOutcome::Hit { .. }));
+    }
+}
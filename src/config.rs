@@ -1,7 +1,9 @@
 pub const MELEE_ATTACK_SEED: u64 = 0x6faf2f42b2ee28f0;
 pub const MAP_GENERATION_SEED: u64 = 0x5e7d30cd44e8330d;
-//pub const ENTITY_GENERATION_SEED: u64 = 0x97c8e4be8964d095;
+pub const ENTITY_GENERATION_SEED: u64 = 0x97c8e4be8964d095;
 pub const AI_SEED: u64 = 0x3c72906cc95045bb;
+pub const THEME_SEED: u64 = 0xd1b54a32d192ed03;
+pub const LOOT_SEED: u64 = 0x8cb92ba72f3d8dd7;
 
 pub const SHOW_MAP_GEN: bool = true;
 
@@ -18,4 +20,18 @@ pub mod map {
     pub const ROOM_MAX_WIDTH: u32 = 11;
     pub const ROOM_MIN_HEIGHT: u32 = 5;
     pub const ROOM_MAX_HEIGHT: u32 = 11;
+
+    pub const DOOR_OPEN_CHANCE: f64 = 0.25;
+
+    /// How many times a builder chain is run (with different seeds) before giving up.
+    pub const MAP_GEN_ATTEMPTS: u32 = 5;
+}
+
+pub mod time {
+    /// Energy an actor with normal speed gains per tick.
+    pub const NORMAL_SPEED: u32 = 100;
+    /// Energy an actor needs before it can act.
+    pub const READY_ENERGY: i32 = 100;
+    /// Energy an ordinary action costs, see [Action::cost](crate::actions::Action::cost).
+    pub const ACTION_COST: u32 = 100;
 }
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+use crate::pieces::death::GameOverSummary;
+
+const FONT_SIZE: f32 = 24.;
+
+/// Marks everything that belongs to the game over screen.
+#[derive(Component)]
+pub struct GameOverScreen;
+
+fn summary_text(summary: &GameOverSummary) -> String {
+    let mut text = format!("You died on depth {}.\n", summary.depth);
+    if let Some(killer) = &summary.killed_by {
+        text.push_str(&format!("Killed by a {killer}.\n"));
+    }
+
+    let kills = summary.kills.0.iter()
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect::<Vec<_>>();
+    match summary.kills.total() {
+        0 => text.push_str("You didn't kill anything.\n"),
+        total => text.push_str(&format!("You killed {total}: {}.\n", kills.join(", "))),
+    }
+
+    text.push_str(&format!("Seed {}\n\nPress R to play again with a new seed.", summary.seed));
+    text
+}
+
+pub fn spawn_game_over_screen(
+    mut commands: Commands,
+    summary: Res<GameOverSummary>,
+) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.8).into(),
+            ..default()
+        },
+        GameOverScreen,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            summary_text(&summary),
+            TextStyle { font_size: FONT_SIZE, color: Color::ORANGE_RED, ..default() },
+        ));
+    });
+}
+
+pub fn despawn_game_over_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<GameOverScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::{actions::GameTime, mapgen::BuildData, pieces::{components::{Fighter, Health}, status::StatusEffects}, player::Player};
+
+const FONT_SIZE: f32 = 16.;
+const PADDING: f32 = 6.;
+const BAR_WIDTH: f32 = 200.;
+const BAR_HEIGHT: f32 = 10.;
+
+/// Marks everything that belongs to the HUD.
+#[derive(Component)]
+pub struct Hud;
+
+#[derive(Component)]
+pub struct HealthText;
+
+#[derive(Component)]
+pub struct HealthBar;
+
+#[derive(Component)]
+pub struct StatsText;
+
+#[derive(Component)]
+pub struct StatusText;
+
+fn text_style(color: Color) -> TextStyle {
+    TextStyle { font_size: FONT_SIZE, color, ..default() }
+}
+
+/// Green when healthy, red when nearly dead.
+fn health_color(fraction: f32) -> Color {
+    match fraction {
+        f if f > 0.5 => Color::LIME_GREEN,
+        f if f > 0.25 => Color::ORANGE,
+        _ => Color::RED,
+    }
+}
+
+pub fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.),
+                left: Val::Px(0.),
+                padding: UiRect::all(Val::Px(PADDING)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        },
+        Hud,
+    ))
+    .with_children(|parent| {
+        parent.spawn((TextBundle::from_section("", text_style(Color::WHITE)), HealthText));
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(BAR_WIDTH),
+                height: Val::Px(BAR_HEIGHT),
+                ..default()
+            },
+            background_color: Color::rgb(0.25, 0.05, 0.05).into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style { height: Val::Percent(100.), ..default() },
+                    ..default()
+                },
+                HealthBar,
+            ));
+        });
+        parent.spawn((TextBundle::from_section("", text_style(Color::SILVER)), StatsText));
+        parent.spawn((TextBundle::from_section("", text_style(Color::GOLD)), StatusText));
+    });
+}
+
+pub fn update_health(
+    player_query: Query<&Health, (With<Player>, Changed<Health>)>,
+    mut text_query: Query<&mut Text, With<HealthText>>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<HealthBar>>,
+) {
+    let Ok(health) = player_query.get_single() else { return };
+    let fraction = health.value as f32 / health.max.max(1) as f32;
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = format!("HP {}/{}", health.value, health.max);
+    }
+    if let Ok((mut style, mut color)) = bar_query.get_single_mut() {
+        style.width = Val::Percent(100. * fraction.clamp(0., 1.));
+        *color = health_color(fraction).into();
+    }
+}
+
+pub fn update_stats(
+    player_query: Query<Ref<Fighter>, With<Player>>,
+    time: Res<GameTime>,
+    build_data: Res<BuildData>,
+    mut text_query: Query<&mut Text, With<StatsText>>,
+) {
+    let Ok(fighter) = player_query.get_single() else { return };
+    let Ok(mut text) = text_query.get_single_mut() else { return };
+    if !fighter.is_changed() && !time.is_changed() && !build_data.is_changed() && !text.is_added() { return; }
+
+    // GameTime counts a tick per turn of an actor with normal speed, like the player.
+    text.sections[0].value = format!("Str {}   Depth {}   Turn {}", fighter.strength, build_data.depth, time.ticks);
+}
+
+/// Lists the player's status effects with the turns they have left.
+pub fn update_status(
+    player_query: Query<&StatusEffects, (With<Player>, Changed<StatusEffects>)>,
+    mut text_query: Query<&mut Text, With<StatusText>>,
+) {
+    let Ok(effects) = player_query.get_single() else { return };
+    let Ok(mut text) = text_query.get_single_mut() else { return };
+
+    text.sections[0].value = effects.0.iter()
+        .map(|e| format!("{} ({})", e.kind, e.turns))
+        .collect::<Vec<_>>()
+        .join("   ");
+}
+
+pub fn despawn_hud(
+    mut commands: Commands,
+    query: Query<Entity, With<Hud>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
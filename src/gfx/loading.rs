@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::mapgen::systems::MapGenProgress;
+
+const FONT_SIZE: f32 = 24.;
+
+/// Marks everything that belongs to the loading screen.
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+pub fn spawn_loading_screen(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), LoadingScreen));
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        LoadingScreen,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section(
+                "Generating map...",
+                TextStyle { font_size: FONT_SIZE, color: Color::OLIVE, ..default() },
+            ),
+            LoadingText,
+        ));
+    });
+}
+
+pub fn update_loading_screen(
+    progress: Option<Res<MapGenProgress>>,
+    mut query: Query<&mut Text, With<LoadingText>>,
+) {
+    let Some(progress) = progress else { return };
+    let Ok(mut text) = query.get_single_mut() else { return };
+
+    let p = progress.0.get();
+    let mut value = format!(
+        "Generating map...\n{} ({}/{}), {} snapshots",
+        p.builder, p.stage, p.total_stages, p.snapshots
+    );
+    if p.attempt > 0 {
+        value.push_str(&format!("\nretry {}", p.attempt));
+    }
+    text.sections[0].value = value;
+}
+
+pub fn despawn_loading_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use crate::messages::{MessageKind, MessageLog};
+
+const FONT_SIZE: f32 = 16.;
+const LINE_HEIGHT: f32 = 20.;
+const PADDING: f32 = 4.;
+/// Lines of the panel at the bottom of the window.
+const PANEL_LINES: usize = 5;
+/// Lines of the full history view, which covers the whole window.
+const HISTORY_LINES: usize = 27;
+
+/// Bevy [Resource] with what part of the [MessageLog] is on screen.
+#[derive(Resource, Default, Debug)]
+pub struct MessageLogView {
+    /// Number of messages scrolled back from the newest one.
+    pub scroll: usize,
+    /// Whether the full history view is open instead of the panel.
+    pub history: bool,
+}
+
+impl MessageLogView {
+    pub fn lines(&self) -> usize {
+        if self.history { HISTORY_LINES } else { PANEL_LINES }
+    }
+
+    /// Scrolls back (positive `lines`) or forward, without going past either end of
+    /// a log of `len` messages.
+    pub fn scroll_by(&mut self, lines: isize, len: usize) {
+        let max = len.saturating_sub(self.lines());
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    pub fn toggle_history(&mut self) {
+        self.history = !self.history;
+        self.scroll = 0;
+    }
+}
+
+#[derive(Component)]
+pub struct MessageLogPanel;
+
+#[derive(Component)]
+pub struct MessageLogText;
+
+fn kind_color(kind: MessageKind) -> Color {
+    match kind {
+        MessageKind::Info => Color::SILVER,
+        MessageKind::Warning => Color::GRAY,
+        MessageKind::Attack => Color::WHITE,
+        MessageKind::Hurt => Color::ORANGE_RED,
+        MessageKind::Death => Color::CRIMSON,
+        MessageKind::Loot => Color::GOLD,
+    }
+}
+
+fn panel_height(view: &MessageLogView) -> Val {
+    if view.history {
+        Val::Percent(100.)
+    } else {
+        Val::Px(PANEL_LINES as f32 * LINE_HEIGHT + 2. * PADDING)
+    }
+}
+
+pub fn spawn_message_log(
+    mut commands: Commands,
+    mut view: ResMut<MessageLogView>,
+) {
+    *view = MessageLogView::default();
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(0.),
+                left: Val::Px(0.),
+                width: Val::Percent(100.),
+                height: panel_height(&view),
+                padding: UiRect::all(Val::Px(PADDING)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        },
+        MessageLogPanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((TextBundle::default(), MessageLogText));
+    });
+}
+
+/// Shows the messages the [MessageLogView] is scrolled to, newest at the bottom.
+pub fn update_message_log(
+    log: Res<MessageLog>,
+    view: Res<MessageLogView>,
+    added: Query<(), Added<MessageLogText>>,
+    mut panel_query: Query<&mut Style, With<MessageLogPanel>>,
+    mut text_query: Query<&mut Text, With<MessageLogText>>,
+) {
+    if !log.is_changed() && !view.is_changed() && added.is_empty() { return; }
+    let Ok(mut style) = panel_query.get_single_mut() else { return };
+    let Ok(mut text) = text_query.get_single_mut() else { return };
+
+    style.height = panel_height(&view);
+
+    let lines = view.lines();
+    let end = log.len() - view.scroll.min(log.len().saturating_sub(lines));
+    let start = end.saturating_sub(lines);
+
+    let mut sections = Vec::new();
+    if view.history {
+        let title = match log.is_empty() {
+            true => "No messages yet (M to close)".to_string(),
+            false => format!("Messages {}-{} of {} (PgUp/PgDn to scroll, M to close)\n", start + 1, end, log.len()),
+        };
+        sections.push(TextSection::new(title, TextStyle { font_size: FONT_SIZE, color: Color::OLIVE, ..default() }));
+    }
+    for (i, message) in log.iter().skip(start).take(end - start).enumerate() {
+        let newline = if i > 0 { "\n" } else { "" };
+        sections.push(TextSection::new(
+            format!("{newline}{message}"),
+            TextStyle { font_size: FONT_SIZE, color: kind_color(message.kind), ..default() },
+        ));
+    }
+    text.sections = sections;
+}
+
+pub fn despawn_message_log(
+    mut commands: Commands,
+    query: Query<Entity, With<MessageLogPanel>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_is_clamped() {
+        let mut view = MessageLogView::default();
+        view.scroll_by(3, 20);
+        assert_eq!(view.scroll, 3);
+        // The oldest message stays on the last line of the panel.
+        view.scroll_by(100, 20);
+        assert_eq!(view.scroll, 20 - PANEL_LINES);
+        view.scroll_by(-4, 20);
+        assert_eq!(view.scroll, 16 - PANEL_LINES);
+        view.scroll_by(-100, 20);
+        assert_eq!(view.scroll, 0);
+
+        // Nothing to scroll if everything fits.
+        view.scroll_by(1, PANEL_LINES);
+        assert_eq!(view.scroll, 0);
+        view.scroll_by(1, 0);
+        assert_eq!(view.scroll, 0);
+    }
+
+    #[test]
+    fn history_scrolls_by_its_own_lines() {
+        let mut view = MessageLogView { scroll: 10, history: false };
+        view.toggle_history();
+        assert!(view.history);
+        assert_eq!(view.scroll, 0);
+
+        view.scroll_by(100, 40);
+        assert_eq!(view.scroll, 40 - HISTORY_LINES);
+        view.scroll_by(1, HISTORY_LINES);
+        assert_eq!(view.scroll, 0);
+    }
+}
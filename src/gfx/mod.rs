@@ -1,14 +1,20 @@
 mod assets;
 mod tiles;
 mod pieces;
+mod loading;
+mod game_over;
+mod hud;
+pub mod message_log;
 
 use bevy::prelude::*;
 
-use crate::{board::components::Position, state::MainState, mapgen::MapGenSet};
+use crate::{board::{components::Position, Board}, state::MainState, mapgen::MapGenSet};
 
 pub const TILE_SIZE: f32 = 32.;
 pub const TILE_Z: f32 = 0.;
 pub const PIECE_Z: f32 = 10.;
+/// Pieces that don't block the tile (corpses, items) are drawn below the others.
+pub const FLOOR_PIECE_Z: f32 = 5.;
 pub const PIECE_SPEED: f32 = 10.;
 pub const POSITION_TOLERANCE: f32 = 0.1;
 
@@ -26,10 +32,22 @@ impl Plugin for GraphicsPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<GraphicsWaitEvent>()
+            .init_resource::<message_log::MessageLogView>()
             .add_systems(Startup, assets::load_assets)
+            .add_systems(OnEnter(MainState::Generating), loading::spawn_loading_screen)
+            .add_systems(Update, loading::update_loading_screen.run_if(in_state(MainState::Generating)))
+            .add_systems(OnExit(MainState::Generating), loading::despawn_loading_screen)
+            .add_systems(OnEnter(MainState::Generating), (message_log::despawn_message_log, hud::despawn_hud))
+            .add_systems(OnEnter(MainState::Game), message_log::spawn_message_log.in_set(MapGenSet::Spawning))
+            .add_systems(Update, message_log::update_message_log)
+            .add_systems(OnEnter(MainState::Game), hud::spawn_hud.in_set(MapGenSet::Spawning))
+            .add_systems(Update, (hud::update_health, hud::update_stats, hud::update_status).run_if(any_with_component::<hud::Hud>()))
+            .add_systems(OnEnter(MainState::GameOver), game_over::spawn_game_over_screen)
+            .add_systems(OnExit(MainState::GameOver), game_over::despawn_game_over_screen)
             .add_systems(Update, pieces::spawn_piece_renderer)
             .add_systems(OnEnter(MainState::Game), tiles::spawn_tile_renderer.in_set(MapGenSet::Spawning))
-            .add_systems(Update, pieces::update_piece_position);
+            .add_systems(Update, pieces::update_piece_position)
+            .add_systems(Update, tiles::update_tile_renderer.run_if(resource_changed::<Board>()));
 
     }
 }
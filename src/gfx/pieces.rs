@@ -1,23 +1,27 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, ecs::query::Has};
 
-use crate::{pieces::components::Piece, board::components::Position};
+use crate::{pieces::components::{Piece, Glyph, TileOccupier}, board::components::Position};
 
-use super::{TILE_SIZE, PIECE_Z, GraphicsAssets, POSITION_TOLERANCE, PIECE_SPEED};
+use super::{TILE_SIZE, PIECE_Z, FLOOR_PIECE_Z, GraphicsAssets, POSITION_TOLERANCE, PIECE_SPEED};
+
+type NewPiece<'a> = (Entity, &'a Position, &'a Piece, Option<&'a Glyph>, Has<TileOccupier>);
 
 pub fn spawn_piece_renderer(
     mut commands: Commands,
-    query: Query<(Entity, &Position, &Piece), Added<Piece>>,
+    query: Query<NewPiece, Added<Piece>>,
     assets: Res<GraphicsAssets>,
 ) {
-    for (entity, pos, piece) in query.iter() {
-        let sprite_idx = match piece.kind.as_str() {
-            "Player" => 1,
+    for (entity, pos, piece, glyph, is_occupier) in query.iter() {
+        let sprite_idx = match (piece.kind.as_str(), glyph) {
+            (_, Some(glyph)) => glyph.0,
+            ("Player", _) => 1,
             _ => 63, // "?"
         };
         let mut sprite = TextureAtlasSprite::new(sprite_idx);
         sprite.custom_size = Some(Vec2::splat(TILE_SIZE));
         sprite.color = Color::WHITE;
-        let v = super::get_world_position(&pos, PIECE_Z);
+        let z = if is_occupier { PIECE_Z } else { FLOOR_PIECE_Z };
+        let v = super::get_world_position(&pos, z);
         commands.entity(entity)
             .insert(
                 SpriteSheetBundle {
@@ -37,7 +41,7 @@ pub fn update_piece_position(
 ) {
     let mut animating = false;
     for (pos, mut transf) in query.iter_mut() {
-        let target = super::get_world_position(&pos, PIECE_Z);
+        let target = super::get_world_position(&pos, transf.translation.z);
         let d = (target - transf.translation).length();
         if d > POSITION_TOLERANCE {
             transf.translation = transf.translation.lerp(
@@ -1,28 +1,35 @@
 use bevy::prelude::*;
 
-use crate::board::{components::{Position, Tile}, Board};
+use crate::{board::{components::{Position, Tile}, Board}, mapgen::BuildData, point::Point, theme::{ActiveTheme, TileLook}};
 
 use super::{GraphicsAssets, TILE_SIZE, TILE_Z};
 
+fn tile_look(theme: &ActiveTheme, build_data: &BuildData, board: &Board, p: Point) -> TileLook {
+    let i = board.xy_to_index(p.x as u32, p.y as u32);
+    let tile = board.get_tile(i);
+    let feature = build_data.decorations.as_ref().and_then(|d| d[i]);
+    theme.0.decorated_look(tile, feature)
+}
+
 pub fn spawn_tile_renderer(
     mut commands: Commands,
     board: Res<Board>,
+    build_data: Res<BuildData>,
+    theme: Res<ActiveTheme>,
     assets: Res<GraphicsAssets>,
 ) {
     for y in 0..board.height {
         for x in 0..board.width {
             let tile = board.get_tile_xy(x, y);
+            let look = tile_look(&theme, &build_data, &board, (x, y).into());
 
-            let idx = match tile {
-                Tile::Floor => 177,
-                Tile::Wall => 219,
-            };
-            let mut sprite = TextureAtlasSprite::new(idx);
+            let mut sprite = TextureAtlasSprite::new(look.glyph);
             sprite.custom_size = Some(Vec2::splat(TILE_SIZE));
-            sprite.color = Color::OLIVE;
-            let v = super::get_world_position(&Position { p: (x, y).into() }, TILE_Z);
+            sprite.color = look.color();
+            let position = Position { p: (x, y).into() };
+            let v = super::get_world_position(&position, TILE_Z);
 
-            commands.spawn_empty()
+            commands.spawn((position, tile))
                 .insert(
                     SpriteSheetBundle {
                         sprite,
@@ -34,3 +41,21 @@ pub fn spawn_tile_renderer(
         }
     }
 }
+
+/// Keeps the tile sprites in sync with the [Board], e.g. when a door is opened or closed.
+pub fn update_tile_renderer(
+    mut query: Query<(&Position, &mut Tile, &mut TextureAtlasSprite)>,
+    board: Res<Board>,
+    build_data: Res<BuildData>,
+    theme: Res<ActiveTheme>,
+) {
+    for (pos, mut tile, mut sprite) in query.iter_mut() {
+        let current = board.get_tile_xy(pos.p.x as u32, pos.p.y as u32);
+        if *tile != current {
+            *tile = current;
+            let look = tile_look(&theme, &build_data, &board, pos.p);
+            sprite.index = look.glyph;
+            sprite.color = look.color();
+        }
+    }
+}
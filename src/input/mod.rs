@@ -2,7 +2,7 @@ use std::{collections::VecDeque, fmt::Display};
 
 use bevy::{prelude::*, app::AppExit};
 
-use crate::{point::Point, board::components::Position, player::Player, state::GameState, pieces::components::Actor, actions::{ActorQueue, models::MoveToAction}};
+use crate::{point::Point, player::Player, state::{GameState, MainState}, pieces::components::Actor, actions::{ActorQueue, models::BumpAction}, gfx::message_log::MessageLogView, messages::MessageLog};
 
 const DIR_KEY_MAP: [(KeyCode, Point); 20] = [
     // wsad movement
@@ -26,7 +26,12 @@ impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<PlayerInputReadyEvent>()
-            .add_systems(Update, handle_movement_keys.run_if(in_state(GameState::PlayerInput)))
+            .add_event::<RestartGameEvent>()
+            .add_systems(Update, handle_movement_keys
+                .run_if(in_state(GameState::PlayerInput))
+                .run_if(history_closed))
+            .add_systems(Update, handle_game_over_keys.run_if(in_state(MainState::GameOver)))
+            .add_systems(Update, handle_message_log_keys)
             .add_systems(Update, bevy::window::close_on_esc);
     }
 }
@@ -34,17 +39,58 @@ impl Plugin for InputPlugin {
 #[derive(Event)]
 pub struct PlayerInputReadyEvent;
 
+/// Sent when the player asks for a new game after dying.
+#[derive(Event)]
+pub struct RestartGameEvent;
+
+fn handle_game_over_keys(
+    keys: Res<Input<KeyCode>>,
+    mut ev_restart: EventWriter<RestartGameEvent>,
+) {
+    if keys.just_pressed(KeyCode::R) {
+        ev_restart.send(RestartGameEvent);
+    }
+}
+
+/// The keys move the player only while the full message history isn't covering
+/// the board.
+fn history_closed(view: Res<MessageLogView>) -> bool {
+    !view.history
+}
+
+/// M opens or closes the full message history, PgUp/PgDn scroll and End jumps
+/// back to the newest message.
+fn handle_message_log_keys(
+    keys: Res<Input<KeyCode>>,
+    log: Res<MessageLog>,
+    mut view: ResMut<MessageLogView>,
+) {
+    if keys.just_pressed(KeyCode::M) {
+        view.toggle_history();
+    }
+    let page = view.lines() as isize;
+    if keys.just_pressed(KeyCode::PageUp) {
+        view.scroll_by(page, log.len());
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        view.scroll_by(-page, log.len());
+    }
+    if keys.just_pressed(KeyCode::End) {
+        view.scroll = 0;
+    }
+}
+
 fn handle_movement_keys(
     keys: ResMut<Input<KeyCode>>,
-    mut player_query: Query<(Entity, &Position, &mut Actor), With<Player>>,
+    mut player_query: Query<(Entity, &mut Actor), With<Player>>,
     mut queue: ResMut<ActorQueue>,
     mut ev_input: EventWriter<PlayerInputReadyEvent>,
 ) {
     // If number of query items is not == 1, return
-    let Ok((entity, pos, mut actor)) = player_query.get_single_mut() else { return };
+    let Ok((entity, mut actor)) = player_query.get_single_mut() else { return };
     for (key, dir) in DIR_KEY_MAP {
         if !keys.just_pressed(key) { continue; }
-        let action = MoveToAction::new(entity, pos.p + dir);
+        let action = BumpAction::new(entity, dir);
         // action score does not matter for the player
         actor.0 = vec![(Box::new(action), 0)];
         queue.0 = VecDeque::from([entity]);
@@ -18,6 +18,9 @@ mod random;
 mod bitgrid;
 mod rect;
 mod saveload;
+mod theme;
+mod combat;
+mod messages;
 
 #[derive(Resource)]
 pub struct GameSeed(u64);
@@ -32,7 +35,30 @@ impl Default for GameSeed {
     }
 }
 
+/// A prefab or Tiled map to play instead of a generated one, given with `--prefab FILE`.
+#[derive(Resource)]
+pub struct PrefabPath(pub Option<std::path::PathBuf>);
+
+impl Default for PrefabPath {
+    fn default() -> Self {
+        let path = std::env::args()
+            .skip_while(|arg| arg != "--prefab")
+            .nth(1)
+            .map(Into::into);
+        Self(path)
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "--mapgen") {
+        if let Err(e) = mapgen::cli::run(&args[2..]) {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+        return;
+    }
+
     App::new()
         .add_plugins(
             DefaultPlugins.set(
@@ -65,8 +91,10 @@ fn main() {
                 manager::ManagerPlugin,
                 mapgen::MapGenPlugin,
                 camera::CameraPlugin,
+                messages::MessagesPlugin,
             )
         )
         .init_resource::<GameSeed>()
+        .init_resource::<PrefabPath>()
         .run()
 }
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{state::{MainState, GameState}, input::PlayerInputReadyEvent, actions::{ActionsCompleteEvent, InvalidPlayerActionEvent, TickEvent}, gfx::GraphicsWaitEvent};
+use crate::{state::{MainState, GameState}, input::{PlayerInputReadyEvent, RestartGameEvent}, actions::{ActionsCompleteEvent, ActorQueue, GameTime, InvalidPlayerActionEvent, PendingActions, TickEvent}, gfx::GraphicsWaitEvent, messages::{MessageKind, MessageLog}, board::components::Tile, pieces::components::Piece, GameSeed};
 
 /// This [Plugin] puts together the scheduling/flow of the game logic during gameplay. 
 pub struct ManagerPlugin;
@@ -12,7 +12,8 @@ impl Plugin for ManagerPlugin {
             .add_systems(Update, turn_update_start.run_if(on_event::<PlayerInputReadyEvent>()))
             .add_systems(Update, turn_update_end.run_if(on_event::<ActionsCompleteEvent>()))
             .add_systems(Update, turn_update_cancel.run_if(on_event::<InvalidPlayerActionEvent>()))
-            .add_systems(Update, tick.run_if(in_state(GameState::TurnUpdate)));
+            .add_systems(Update, tick.run_if(in_state(GameState::TurnUpdate)))
+            .add_systems(Update, restart_game.run_if(on_event::<RestartGameEvent>()));
     }
 }
 
@@ -36,7 +37,14 @@ fn turn_update_end(mut next_state: ResMut<NextState<GameState>>) {
     next_state.set(GameState::PlayerInput);
 }
 
-fn turn_update_cancel(mut next_state: ResMut<NextState<GameState>>) {
+fn turn_update_cancel(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut ev_invalid: EventReader<InvalidPlayerActionEvent>,
+    mut log: ResMut<MessageLog>,
+) {
+    for ev in ev_invalid.iter() {
+        log.push(MessageKind::Warning, ev.0.to_string());
+    }
     next_state.set(GameState::PlayerInput);
 }
 
@@ -48,3 +56,28 @@ fn tick(
         ev_tick.send(TickEvent);
     }
 }
+
+/// Everything that belongs to a game and goes when it's restarted.
+type GameEntities = Or<(With<Piece>, With<Tile>, With<Camera>)>;
+
+/// Clears out the finished game and generates a new map with a new [GameSeed].
+fn restart_game(
+    mut commands: Commands,
+    query: Query<Entity, GameEntities>,
+    mut queue: ResMut<ActorQueue>,
+    mut pending: ResMut<PendingActions>,
+    mut next_state: ResMut<NextState<MainState>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    queue.0.clear();
+    pending.0.clear();
+    commands.insert_resource(GameTime::default());
+    commands.insert_resource(MessageLog::default());
+
+    let seed = rand::random();
+    info!("starting a new game with seed {seed}");
+    commands.insert_resource(GameSeed(seed));
+    next_state.set(MainState::Generating);
+}
@@ -0,0 +1,262 @@
+use std::fmt::Display;
+
+use crate::{board::{Board, components::Tile}, point::Point};
+
+use super::{BuildData, common};
+
+/// Summary statistics of a generated map, see [analyze].
+#[derive(Debug, Clone)]
+pub struct MapReport {
+    /// Fraction of all tiles that are not walls.
+    pub floor_ratio: f32,
+    pub room_count: usize,
+    pub room_sizes: Option<SizeStats>,
+    /// Number of separate areas of non-wall tiles (moving in eight directions).
+    pub connected_components: usize,
+    /// Non-wall tiles outside of rooms with exactly one cardinal non-wall neighbour.
+    pub dead_ends: usize,
+    pub average_corridor_length: Option<f32>,
+    /// Steps from the starting position to the farthest reachable tile.
+    pub max_start_distance: Option<u32>,
+    pub room_graph: Option<GraphStats>,
+}
+
+/// Distribution of room areas, in tiles.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeStats {
+    pub min: u32,
+    pub max: u32,
+    pub mean: f32,
+    pub median: u32,
+}
+
+/// Topology of the room graph, see [RoomGraph](super::room_graph::RoomGraph).
+#[derive(Debug, Clone, Copy)]
+pub struct GraphStats {
+    pub leaf_rooms: usize,
+    pub chokepoints: usize,
+    /// Passages that split the map in two when removed.
+    pub chokepoint_passages: usize,
+    pub cycles: usize,
+}
+
+/// A broken invariant found by [validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The outer edge of the board is not solid wall.
+    OpenBorder(Point),
+    NoStartingPosition,
+    StartNotWalkable(Point),
+    /// Non-wall tiles that can't be reached from the starting position.
+    UnreachableFloor { count: usize, example: Point },
+}
+
+fn is_open(board: &Board, i: usize) -> bool {
+    board.get_tile(i) != Tile::Wall
+}
+
+fn count_components(board: &Board) -> usize {
+    let mut reached = vec![false; (board.width * board.height) as usize];
+    let mut components = 0;
+
+    for i in 0..reached.len() {
+        if reached[i] || !is_open(board, i) { continue; }
+        components += 1;
+        let start = board.index_to_xy(i).into();
+        for (j, d) in common::distance_map(board, start).into_iter().enumerate() {
+            if d.is_some() {
+                reached[j] = true;
+            }
+        }
+    }
+    components
+}
+
+fn count_dead_ends(build_data: &BuildData) -> usize {
+    let board = &build_data.board;
+    let in_room = |p: Point| build_data.rects.as_ref().is_some_and(|rooms| rooms.iter().any(|r| r.contains(p)));
+
+    (0..(board.width * board.height) as usize)
+        .filter(|i| is_open(board, *i))
+        .map(|i| Point::from(board.index_to_xy(i)))
+        .filter(|p| !in_room(*p))
+        .filter(|p| {
+            Point::CARDINALS.iter()
+                .map(|d| *p + *d)
+                .filter(|n| board.in_bounds_xy(n.x, n.y))
+                .filter(|n| is_open(board, board.xy_to_index(n.x as u32, n.y as u32)))
+                .count() == 1
+        })
+        .count()
+}
+
+fn size_stats(sizes: &mut [u32]) -> Option<SizeStats> {
+    if sizes.is_empty() {
+        return None;
+    }
+    sizes.sort();
+    Some(SizeStats {
+        min: sizes[0],
+        max: sizes[sizes.len() - 1],
+        mean: sizes.iter().sum::<u32>() as f32 / sizes.len() as f32,
+        median: sizes[sizes.len() / 2],
+    })
+}
+
+/// Computes the [MapReport] of a finished map.
+pub fn analyze(build_data: &BuildData) -> MapReport {
+    let board = &build_data.board;
+    let total = (board.width * board.height) as usize;
+    let open = (0..total).filter(|i| is_open(board, *i)).count();
+
+    let mut room_sizes = build_data.rects.as_ref()
+        .map(|rooms| rooms.iter().map(|r| r.width() * r.height()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let average_corridor_length = build_data.corridors.as_ref()
+        .filter(|c| !c.is_empty())
+        .map(|c| c.iter().map(|c| c.len()).sum::<usize>() as f32 / c.len() as f32);
+
+    let max_start_distance = build_data.starting_position
+        .map(|start| common::distance_map(board, start))
+        .and_then(|d| d.into_iter().flatten().max());
+
+    let room_graph = build_data.room_graph.as_ref().map(|g| GraphStats {
+        leaf_rooms: g.leaves.len(),
+        chokepoints: g.chokepoints.len(),
+        chokepoint_passages: g.passages.iter().filter(|p| p.is_chokepoint).count(),
+        cycles: g.cycles,
+    });
+
+    MapReport {
+        floor_ratio: open as f32 / total as f32,
+        room_count: room_sizes.len(),
+        room_sizes: size_stats(&mut room_sizes),
+        connected_components: count_components(board),
+        dead_ends: count_dead_ends(build_data),
+        average_corridor_length,
+        max_start_distance,
+        room_graph,
+    }
+}
+
+/// Checks the invariants every playable map must satisfy. Returns an empty list if
+/// the map is valid.
+pub fn validate(build_data: &BuildData) -> Vec<Violation> {
+    let board = &build_data.board;
+    let mut violations = Vec::new();
+
+    let border = board.iter_points()
+        .filter(|p| p.x == 0 || p.y == 0 || p.x == board.width as i32 - 1 || p.y == board.height as i32 - 1);
+    for p in border {
+        if board.get_tile_xy(p.x as u32, p.y as u32) != Tile::Wall {
+            violations.push(Violation::OpenBorder(p));
+            break;
+        }
+    }
+
+    let Some(start) = build_data.starting_position else {
+        violations.push(Violation::NoStartingPosition);
+        return violations;
+    };
+
+    if !board.is_walkable(start) {
+        violations.push(Violation::StartNotWalkable(start));
+    }
+
+    let distances = common::distance_map(board, start);
+    let unreachable = (0..distances.len())
+        .filter(|i| is_open(board, *i) && distances[*i].is_none())
+        .collect::<Vec<_>>();
+    if let Some(&first) = unreachable.first() {
+        violations.push(Violation::UnreachableFloor {
+            count: unreachable.len(),
+            example: board.index_to_xy(first).into(),
+        });
+    }
+
+    violations
+}
+
+impl Display for MapReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "floor {:.1}%, {} rooms", self.floor_ratio * 100., self.room_count)?;
+        if let Some(s) = self.room_sizes {
+            write!(f, " (area {}..{}, mean {:.1}, median {})", s.min, s.max, s.mean, s.median)?;
+        }
+        write!(f, ", {} components, {} dead ends", self.connected_components, self.dead_ends)?;
+        if let Some(len) = self.average_corridor_length {
+            write!(f, ", corridor length {len:.1}")?;
+        }
+        if let Some(d) = self.max_start_distance {
+            write!(f, ", farthest tile {d}")?;
+        }
+        if let Some(g) = self.room_graph {
+            write!(
+                f, ", {} leaf rooms, {} chokepoint rooms, {} chokepoint passages, {} cycles",
+                g.leaf_rooms, g.chokepoints, g.chokepoint_passages, g.cycles
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::OpenBorder(p) => write!(f, "border is not solid wall at ({}, {})", p.x, p.y),
+            Violation::NoStartingPosition => write!(f, "no starting position"),
+            Violation::StartNotWalkable(p) => write!(f, "starting position ({}, {}) is not walkable", p.x, p.y),
+            Violation::UnreachableFloor { count, example } => write!(
+                f, "{count} floor tiles unreachable from the start, e.g. ({}, {})", example.x, example.y
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::map::MAP_GEN_ATTEMPTS, mapgen::{named_chain, prefab, BUILDER_NAMES}};
+
+    const SEEDS: [u64; 6] = [0, 1, 42, 1337, 0xdead_beef, u64::MAX];
+
+    /// Every chain has to make a valid map within its retries, without needing
+    /// the fallback builder.
+    #[test]
+    fn builders_make_valid_maps() {
+        for name in BUILDER_NAMES {
+            for seed in SEEDS {
+                let mut chain = named_chain(name, seed, 1).unwrap();
+                let build_data = (0..MAP_GEN_ATTEMPTS)
+                    .find_map(|attempt| chain.try_build(attempt).ok())
+                    .unwrap_or_else(|| panic!("{name} with seed {seed} failed every attempt"));
+                let violations = validate(&build_data);
+                assert!(violations.is_empty(), "{name} with seed {seed}: {violations:?}");
+            }
+        }
+    }
+
+    fn build_data(text: &str) -> BuildData {
+        let prefab = prefab::parse(text, 0).unwrap();
+        let mut build_data = BuildData::new(0, prefab.board.width, prefab.board.height);
+        build_data.board = prefab.board;
+        build_data.starting_position = prefab.starting_position;
+        build_data
+    }
+
+    #[test]
+    fn validate_finds_violations() {
+        let valid = build_data("#####\n#@..#\n#####\n");
+        assert!(validate(&valid).is_empty());
+
+        let unreachable = build_data("######\n#@#..#\n######\n");
+        assert_eq!(validate(&unreachable), vec![Violation::UnreachableFloor { count: 2, example: Point::new(3, 1) }]);
+
+        let open_border = build_data("#####\n#@...\n#####\n");
+        assert_eq!(validate(&open_border), vec![Violation::OpenBorder(Point::new(4, 1))]);
+
+        let no_start = build_data("#####\n#...#\n#####\n");
+        assert_eq!(validate(&no_start), vec![Violation::NoStartingPosition]);
+    }
+}
@@ -0,0 +1,33 @@
+use bevy::prelude::debug;
+
+use crate::{random::PRng, point::Point, board::components::Tile};
+
+use super::{MetaBuilder, BuildData, MapGenError};
+
+/// Starts on the floor tile closest to the centre of the board. Useful for maps
+/// without rooms, such as caves.
+pub(super) struct AreaStartingPosition {}
+
+impl AreaStartingPosition {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
+
+impl MetaBuilder for AreaStartingPosition {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let board = &build_data.board;
+        let center = Point::new(board.width as i32 / 2, board.height as i32 / 2);
+
+        let start = board.iter_points()
+            .filter(|p| board.get_tile_xy(p.x as u32, p.y as u32) == Tile::Floor)
+            .min_by_key(|p| (p.dist_manhattan(center), *p));
+
+        let Some(start) = start else {
+            return Err(MapGenError::NoFloor { builder: std::any::type_name::<Self>() });
+        };
+        build_data.starting_position = Some(start);
+        debug!("build_data starting position set");
+        Ok(())
+    }
+}
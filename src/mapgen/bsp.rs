@@ -1,18 +1,25 @@
 use crate::{rect::Rect, random::PRng, board::{components::Tile, Board}};
 
-use super::{common, InitBuilder, BuildData};
+use super::{common, InitBuilder, BuildData, MapGenError};
 
 const NUM_TRIES: u32 = 600;
 const MAX_SUBRECT_WIDTH: u32 = 10;
 const MAX_SUBRECT_HEIGHT: u32 = 10;
+const MIN_BOARD_SIZE: u32 = 12;
 
 pub struct BspMapBuilder {
     rects: Vec<Rect>,
+    corridors: bool,
 }
 
 impl InitBuilder for BspMapBuilder {
 
-    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let (width, height) = (build_data.board.width, build_data.board.height);
+        if width < MIN_BOARD_SIZE || height < MIN_BOARD_SIZE {
+            return Err(MapGenError::BoardTooSmall { builder: std::any::type_name::<Self>(), width, height });
+        }
+
         let mut rects: Vec<Rect> = Vec::new();
 
         self.rects.clear();
@@ -42,31 +49,44 @@ impl InitBuilder for BspMapBuilder {
             n_rects += 1;
         }
 
-        for i in 0..rects.len() - 1 {
-            let r = rects[i];
-            let next_r = rects[i + 1];
+        if rects.is_empty() {
+            return Err(MapGenError::NoRooms { builder: std::any::type_name::<Self>() });
+        }
+        build_data.rects = Some(rects.clone());
+        if !self.corridors { return Ok(()); }
+
+        let mut corridors: Vec<Vec<usize>> = Vec::new();
+
+        for pair in rects.windows(2) {
+            let (r, next_r) = (pair[0], pair[1]);
             let start_x = r.x1 + rng.gen_range(0..u32::abs_diff(r.x1, r.x2));
             let start_y = r.y1 + rng.gen_range(0..u32::abs_diff(r.y1, r.y2));
             let end_x = next_r.x1 + rng.gen_range(0..u32::abs_diff(next_r.x1, next_r.x2));
             let end_y = next_r.y1 + rng.gen_range(0..u32::abs_diff(next_r.y1, next_r.y2));
 
-            common::draw_corridor(
+            corridors.push(common::draw_corridor(
                 &mut build_data.board, 
                 (start_x, start_y).into(), 
                 (end_x, end_y).into(), 
                 true
-            );
+            ));
 
             build_data.take_snapshot();
         }
-        build_data.rects = Some(rects);
+        build_data.corridors = Some(corridors);
+        Ok(())
     }
 }
 
 impl BspMapBuilder {
-    #[allow(dead_code)]
     pub fn new() -> Box<BspMapBuilder> {
-        Box::new(BspMapBuilder { rects: Vec::new() })
+        Box::new(BspMapBuilder { rects: Vec::new(), corridors: true })
+    }
+
+    /// Only place the rooms, leaving the corridors to a [MetaBuilder](super::MetaBuilder)
+    /// such as [RoomCorridors](super::room_corridors::RoomCorridors).
+    pub fn rooms_only() -> Box<BspMapBuilder> {
+        Box::new(BspMapBuilder { rects: Vec::new(), corridors: false })
     }
 
     /// Panics if self.rects.len() == 0
@@ -1,15 +1,21 @@
 use crate::{random::PRng, rect::Rect, board::components::Tile};
 
-use super::{common, InitBuilder, BuildData};
+use super::{common, InitBuilder, BuildData, MapGenError};
 
 const MIN_ROOM_SIZE: u32 = 8; // Must be at least 4 (probably?)
+const MIN_BOARD_SIZE: u32 = 6;
 
 pub struct BspInteriorBuilder {
     rects: Vec<Rect>,
+    corridors: bool,
 }
 
 impl InitBuilder for BspInteriorBuilder {
-    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let (width, height) = (build_data.board.width, build_data.board.height);
+        if width < MIN_BOARD_SIZE || height < MIN_BOARD_SIZE {
+            return Err(MapGenError::BoardTooSmall { builder: std::any::type_name::<Self>(), width, height });
+        }
 
         let mut rooms: Vec<Rect> = Vec::new();
 
@@ -33,34 +39,44 @@ impl InitBuilder for BspInteriorBuilder {
         }
 
         build_data.take_snapshot();
+        build_data.rects = Some(rooms.clone());
+        if !self.corridors { return Ok(()); }
+
+        let mut corridors: Vec<Vec<usize>> = Vec::new();
 
         // Corridors.
-        for i in 0..rooms.len() - 1 {
-            let r = rooms[i];
-            let next = rooms[i + 1];
+        for pair in rooms.windows(2) {
+            let (r, next) = (pair[0], pair[1]);
             let from_x = r.x1 + rng.gen_range(0..r.width());
             let from_y = r.y1 + rng.gen_range(0..r.height());
             let to_x = next.x1 + rng.gen_range(0..next.width());
             let to_y = next.y1 + rng.gen_range(0..next.height());
 
-            common::draw_corridor(
+            corridors.push(common::draw_corridor(
                 &mut build_data.board, 
                 (from_x, from_y).into(), 
                 (to_x, to_y).into(),
                 true
-            );
+            ));
 
             build_data.take_snapshot();
         }
 
-        build_data.rects = Some(rooms);
+        build_data.corridors = Some(corridors);
+        Ok(())
     }
 }
 
 impl BspInteriorBuilder {
-    #[allow(dead_code)]
     pub fn new() -> Box<Self> {
-        Box::new(Self { rects: Vec::new() })
+        Box::new(Self { rects: Vec::new(), corridors: true })
+    }
+
+    /// Only place the rooms, leaving the corridors to a [MetaBuilder](super::MetaBuilder)
+    /// such as [RoomCorridors](super::room_corridors::RoomCorridors).
+    #[allow(dead_code)]
+    pub fn rooms_only() -> Box<Self> {
+        Box::new(Self { rects: Vec::new(), corridors: false })
     }
 
     fn add_subrects(&mut self, rect: Rect, rng: &mut PRng) {
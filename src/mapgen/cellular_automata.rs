@@ -1,6 +1,6 @@
 use crate::{point, board::components::Tile, random::PRng};
 
-use super::{BuildData, InitBuilder, MetaBuilder};
+use super::{BuildData, InitBuilder, MetaBuilder, MapGenError};
 
 const PERCENT_FLOOR: u32 = 55;
 const NUM_ITERATIONS: u32 = 15;
@@ -9,7 +9,6 @@ pub struct CellularAutomataBuilder {}
 
 impl CellularAutomataBuilder {
 
-    #[allow(dead_code)]
     pub fn new() -> Box<Self> {
         Box::new(CellularAutomataBuilder {})
     }
@@ -46,7 +45,7 @@ impl CellularAutomataBuilder {
 }
 
 impl InitBuilder for CellularAutomataBuilder {
-    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
         // Randomize the map (keeping a border)
         for y in 1..build_data.board.height - 1 {
             for x in 1..build_data.board.width - 1 {
@@ -61,11 +60,13 @@ impl InitBuilder for CellularAutomataBuilder {
         for _ in 0..NUM_ITERATIONS {
             Self::iterate(build_data);
         }
+        Ok(())
     }
 }
 
 impl MetaBuilder for CellularAutomataBuilder {
-    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
         Self::iterate(build_data);
+        Ok(())
     }
 }
@@ -0,0 +1,249 @@
+//! Headless map generation, run with `bevogst --mapgen [OPTIONS]`. Builds maps for a
+//! range of seeds without opening a window and prints them as ASCII or writes PNGs.
+//! With `--report` and `--validate` the maps are analysed instead, see [analysis].
+//! `--prefab` loads a hand-made map instead, see [prefab] and [tiled].
+
+use std::{ops::Range, path::PathBuf};
+
+use crate::theme::Themes;
+
+use super::{analysis, export, prefab, tiled, load_map, named_builder, themed_builder, BUILDER_NAMES, BuildData};
+
+const USAGE: &str = "\
+usage: bevogst --mapgen [OPTIONS]
+
+options:
+    --builder NAME   builder chain to run (default: random)
+    --theme NAME     build with a theme's chain and decorations instead, or
+                     `auto` to pick the theme like the game does
+    --prefab FILE    load a prefab or Tiled (.tmx, .tmj) map instead of generating one
+    --seeds A..B     seeds A up to (excluding) B, or a single seed (default: 0)
+    --depth N        map depth (default: 0)
+    --png DIR        write one PNG per seed to DIR instead of printing ASCII
+    --save-prefab DIR
+                     write one prefab per seed to DIR
+    --tiled DIR      write one Tiled map per seed to DIR
+    --tiled-format F format of the Tiled maps, tmx or tmj (default: tmx)
+    --sheet FILE     write a contact sheet of all seeds to FILE
+    --columns N      number of columns in the contact sheet (default: 8)
+    --report         print the quality metrics of every seed
+    --validate       check every seed for broken invariants and list the failures
+    --list           list the available builder chains and themes";
+
+struct Options {
+    builder: String,
+    theme: Option<String>,
+    prefab: Option<PathBuf>,
+    seeds: Range<u64>,
+    depth: u32,
+    png_dir: Option<PathBuf>,
+    prefab_dir: Option<PathBuf>,
+    tiled_dir: Option<PathBuf>,
+    tiled_format: String,
+    sheet: Option<PathBuf>,
+    columns: u32,
+    report: bool,
+    validate: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            builder: "random".to_string(),
+            theme: None,
+            prefab: None,
+            seeds: 0..1,
+            depth: 0,
+            png_dir: None,
+            prefab_dir: None,
+            tiled_dir: None,
+            tiled_format: "tmx".to_string(),
+            sheet: None,
+            columns: 8,
+            report: false,
+            validate: false,
+        }
+    }
+}
+
+fn parse_seeds(arg: &str) -> Result<Range<u64>, String> {
+    let parse = |s: &str| s.parse::<u64>().map_err(|_| format!("invalid seed: {s}"));
+    match arg.split_once("..") {
+        Some((start, end)) => {
+            let range = parse(start)?..parse(end)?;
+            if range.is_empty() {
+                return Err(format!("empty seed range: {arg}"));
+            }
+            Ok(range)
+        },
+        None => {
+            let seed = parse(arg)?;
+            Ok(seed..seed.saturating_add(1))
+        },
+    }
+}
+
+/// Returns `None` if only the list of builders was requested.
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("missing value for {arg}\n\n{USAGE}"));
+        match arg.as_str() {
+            "--builder" => options.builder = value()?.clone(),
+            "--theme" => options.theme = Some(value()?.clone()),
+            "--prefab" => options.prefab = Some(value()?.into()),
+            "--seeds" => options.seeds = parse_seeds(value()?)?,
+            "--depth" => {
+                let v = value()?;
+                options.depth = v.parse().map_err(|_| format!("invalid depth: {v}"))?;
+            },
+            "--png" => options.png_dir = Some(value()?.into()),
+            "--save-prefab" => options.prefab_dir = Some(value()?.into()),
+            "--tiled" => options.tiled_dir = Some(value()?.into()),
+            "--tiled-format" => {
+                let v = value()?;
+                if v != "tmx" && v != "tmj" {
+                    return Err(format!("invalid Tiled format: {v}"));
+                }
+                options.tiled_format = v.clone();
+            },
+            "--sheet" => options.sheet = Some(value()?.into()),
+            "--columns" => {
+                let v = value()?;
+                options.columns = v.parse().ok().filter(|c| *c > 0)
+                    .ok_or(format!("invalid number of columns: {v}"))?;
+            },
+            "--report" => options.report = true,
+            "--validate" => options.validate = true,
+            "--list" => return Ok(None),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument: {arg}\n\n{USAGE}")),
+        }
+    }
+
+    if !BUILDER_NAMES.contains(&options.builder.as_str()) {
+        return Err(format!("unknown builder: {} (see --list)", options.builder));
+    }
+    if let Some(theme) = options.theme.as_deref().filter(|t| *t != "auto") {
+        if Themes::default().get(theme).is_none() {
+            return Err(format!("unknown theme: {theme} (see --list)"));
+        }
+    }
+    Ok(Some(options))
+}
+
+fn generate(options: &Options, themes: &Themes, seed: u64) -> Result<BuildData, String> {
+    if let Some(path) = &options.prefab {
+        return load_map(path, options.depth);
+    }
+
+    // The names were checked when parsing the arguments.
+    Ok(match options.theme.as_deref() {
+        Some("auto") => themed_builder(themes.pick(seed, options.depth), seed, options.depth, None),
+        Some(name) => themed_builder(themes.get(name).unwrap(), seed, options.depth, None),
+        None => named_builder(&options.builder, seed, options.depth).unwrap(),
+    })
+}
+
+fn list(themes: &Themes) {
+    println!("builders:");
+    BUILDER_NAMES.iter().for_each(|name| println!("    {name}"));
+    println!("themes:");
+    for theme in themes.0.iter() {
+        let features = theme.features.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        println!(
+            "    {} (builder {}, spawn table {}, depth {}..={}, features: {})",
+            theme.name, theme.builder, theme.spawn_table, theme.min_depth, theme.max_depth,
+            if features.is_empty() { "none".to_string() } else { features.join(", ") },
+        );
+    }
+}
+
+/// Runs the map generation command line with the arguments following `--mapgen`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let themes = Themes::default();
+    let Some(options) = parse_args(args)? else {
+        list(&themes);
+        return Ok(());
+    };
+
+    for dir in [&options.png_dir, &options.prefab_dir, &options.tiled_dir].into_iter().flatten() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+
+    let prefab_name = options.prefab.as_ref()
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().into_owned());
+    let label = prefab_name.as_deref()
+        .or(options.theme.as_deref())
+        .unwrap_or(&options.builder);
+    let print_ascii = options.sheet.is_none() && !options.report && !options.validate;
+    let mut maps = Vec::new();
+    let mut failed = Vec::new();
+
+    for seed in options.seeds.clone() {
+        let build_data = generate(&options, &themes, seed)?;
+
+        if options.report {
+            println!("seed {seed}: {}", analysis::analyze(&build_data));
+        }
+
+        if options.validate {
+            let violations = analysis::validate(&build_data);
+            if !violations.is_empty() {
+                println!("seed {seed} FAILED");
+                violations.iter().for_each(|v| println!("    {v}"));
+                failed.push(seed);
+            }
+        }
+
+        if let Some(dir) = &options.prefab_dir {
+            let path = dir.join(format!("{label}_{seed}.txt"));
+            std::fs::write(&path, prefab::to_text(&build_data))
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        if let Some(dir) = &options.tiled_dir {
+            let path = dir.join(format!("{label}_{seed}.{}", options.tiled_format));
+            tiled::save(&path, &build_data).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        match &options.png_dir {
+            Some(dir) => {
+                let path = dir.join(format!("{label}_{seed}.png"));
+                export::to_image(&build_data)
+                    .save(&path)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+            },
+            None if print_ascii => {
+                println!("{label} seed {seed} depth {}", options.depth);
+                println!("{}", export::to_ascii(&build_data));
+            },
+            None => {},
+        }
+
+        if options.sheet.is_some() {
+            // Only the final board is needed for the sheet.
+            let mut build_data = build_data;
+            build_data.history.clear();
+            maps.push(build_data);
+        }
+    }
+
+    if let Some(path) = &options.sheet {
+        export::contact_sheet(&maps, options.columns)
+            .save(path)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    if options.validate {
+        let total = options.seeds.end - options.seeds.start;
+        if !failed.is_empty() {
+            return Err(format!("{} of {total} seeds failed validation: {failed:?}", failed.len()));
+        }
+        println!("all {total} seeds passed validation");
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use crate::{random::PRng, rect::Rect, board::{Board, components::Tile}, point::Point};
 
 /// Set all [Tile]s in the given [Rect] to the specified tile type.
@@ -12,8 +14,35 @@ pub fn set_rect(board: &mut Board, rect: &Rect, tile: Tile) {
     }
 }
 
+/// Number of steps (moving in any of the eight directions) from `start` to every tile
+/// of the board, indexed like the board's tiles. Anything but a [Tile::Wall] can be
+/// passed through, so closed doors count as open. Unreachable tiles are `None`.
+pub fn distance_map(board: &Board, start: Point) -> Vec<Option<u32>> {
+    let mut distances = vec![None; (board.width * board.height) as usize];
+    if !board.in_bounds_xy(start.x, start.y) {
+        return distances;
+    }
+
+    let mut queue = VecDeque::from([start]);
+    distances[board.xy_to_index(start.x as u32, start.y as u32)] = Some(0);
+
+    while let Some(p) = queue.pop_front() {
+        let d = distances[board.xy_to_index(p.x as u32, p.y as u32)].unwrap();
+        for dir in Point::OCTANT {
+            let next = p + dir;
+            if !board.in_bounds_xy(next.x, next.y) { continue; }
+            let i = board.xy_to_index(next.x as u32, next.y as u32);
+            if distances[i].is_some() || board.get_tile(i) == Tile::Wall { continue; }
+            distances[i] = Some(d + 1);
+            queue.push_back(next);
+        }
+    }
+    distances
+}
+
 /// Generate a [Rect] of dimensions `min_width <= w < max_width`, `min_height <= h < max_height` at a
 /// random position `(x, y)` where `x_min <= x < x_max - w` and `y_min <= y < y_max - h`.
+/// Returns `None` if any of those ranges is empty, e.g. because the area is too small.
 pub fn random_rect(
     min_width: u32,
     max_width: u32,
@@ -24,12 +53,56 @@ pub fn random_rect(
     y_min: u32,
     y_max: u32,
     rng: &mut PRng,
-) -> Rect {
+) -> Option<Rect> {
+    if min_width == 0 || min_width >= max_width || min_height == 0 || min_height >= max_height {
+        return None;
+    }
     let w: u32 = rng.gen_range(min_width..max_width);
     let h: u32 = rng.gen_range(min_height..max_height);
-    let x: u32 = rng.gen_range(x_min..(x_max - w));
-    let y: u32 = rng.gen_range(y_min..(y_max - h));
-    Rect::new(x, y, w, h)
+    let x_range = x_min..x_max.checked_sub(w)?;
+    let y_range = y_min..y_max.checked_sub(h)?;
+    if x_range.is_empty() || y_range.is_empty() {
+        return None;
+    }
+    let x: u32 = rng.gen_range(x_range);
+    let y: u32 = rng.gen_range(y_range);
+    Some(Rect::new(x, y, w, h))
+}
+
+/// How a corridor is carved between two points.
+#[derive(Clone, Copy, Debug)]
+pub enum CorridorShape {
+    /// An L-shaped corridor, see [draw_corridor].
+    Dogleg,
+    /// A straight Bresenham line. Diagonal steps are filled in so that the corridor
+    /// can also be walked using only cardinal moves.
+    Straight,
+    /// A wandering corridor that drifts towards its destination.
+    Drunk,
+}
+
+/// Chance (in 100) that a step of a drunk corridor is taken towards the destination.
+const DRUNK_TOWARDS_CHANCE: u32 = 65;
+
+/// Carve a corridor of the given `shape` and `width` between the specified coordinates.
+/// Wider corridors grow towards positive x and y and never touch the outer edge of
+/// the board. Returns a list of the corridor's tile indices (only modified indices).
+pub fn carve_corridor(
+    board: &mut Board,
+    source: Point,
+    destination: Point,
+    shape: CorridorShape,
+    width: u32,
+    rng: &mut PRng,
+) -> Vec<usize> {
+    match shape {
+        CorridorShape::Dogleg => {
+            let h_then_v = rng.gen::<bool>();
+            draw_dogleg(board, source, destination, h_then_v, width)
+        },
+        CorridorShape::Straight => draw_straight(board, source, destination, width),
+        CorridorShape::Drunk => draw_drunk(board, source, destination, width, rng),
+    }
 }
 
 /// Draw an L-shaped corridor (i.e. set [Tile::Floor]s) between the specified coordinates.
@@ -40,13 +113,43 @@ pub fn draw_corridor(
     source: Point,
     destination: Point,
     h_then_v: bool,
+) -> Vec<usize> {
+    draw_dogleg(board, source, destination, h_then_v, 1)
+}
+
+fn draw_dogleg(
+    board: &mut Board,
+    source: Point,
+    destination: Point,
+    h_then_v: bool,
+    width: u32,
 ) -> Vec<usize> {
     let (from_x, from_y, to_x, to_y) = (source.x, source.y, destination.x, destination.y);
 
     if h_then_v {
-        draw_corridor_h_then_v(board, from_x, from_y, to_x, to_y)
+        draw_corridor_h_then_v(board, from_x, from_y, to_x, to_y, width)
     } else {
-        draw_corridor_v_then_h(board, from_x, from_y, to_x, to_y)
+        draw_corridor_v_then_h(board, from_x, from_y, to_x, to_y, width)
+    }
+}
+
+/// Set a `width` by `width` square of [Tile::Floor] with its lower left corner at `(x, y)`,
+/// pushing the indices of the modified tiles to `corridor`. The outer edge of the board
+/// is left untouched.
+fn carve(board: &mut Board, x: i32, y: i32, width: u32, corridor: &mut Vec<usize>) {
+    assert!(x >= 0);
+    assert!(y >= 0);
+    for dy in 0..width as i32 {
+        for dx in 0..width as i32 {
+            let (cx, cy) = (x + dx, y + dy);
+            if dx + dy > 0 && (cx < 1 || cy < 1 || cx > board.width as i32 - 2 || cy > board.height as i32 - 2) {
+                continue;
+            }
+            if board.get_tile_xy(cx as u32, cy as u32) != Tile::Floor {
+                board.set_tile_xy(cx as u32, cy as u32, Tile::Floor);
+                corridor.push(board.xy_to_index(cx as u32, cy as u32));
+            }
+        }
     }
 }
 
@@ -56,6 +159,7 @@ fn draw_corridor_v_then_h(
     from_y: i32,
     to_x: i32,
     to_y: i32,
+    width: u32,
 ) -> Vec<usize> {
     let mut x = from_x;
     let mut y = from_y;
@@ -71,12 +175,7 @@ fn draw_corridor_v_then_h(
         } else if y > to_y {
             y -= 1;
         }
-        assert!(x >= 0);
-        assert!(y >= 0);
-        if board.get_tile_xy(x as u32, y as u32) != Tile::Floor {
-            board.set_tile_xy(x as u32, y as u32, Tile::Floor);
-            corridor.push(board.xy_to_index(x as u32, y as u32));
-        }
+        carve(board, x, y, width, &mut corridor);
     }
     corridor
 }
@@ -87,6 +186,7 @@ fn draw_corridor_h_then_v(
     from_y: i32,
     to_x: i32,
     to_y: i32,
+    width: u32,
 ) -> Vec<usize> {
     let mut x = from_x;
     let mut y = from_y;
@@ -102,12 +202,77 @@ fn draw_corridor_h_then_v(
         } else if x > to_x {
             x -= 1;
         }
-        assert!(x >= 0);
-        assert!(y >= 0);
-        if board.get_tile_xy(x as u32, y as u32) != Tile::Floor {
-            board.set_tile_xy(x as u32, y as u32, Tile::Floor);
-            corridor.push(board.xy_to_index(x as u32, y as u32));
+        carve(board, x, y, width, &mut corridor);
+    }
+    corridor
+}
+
+fn draw_straight(board: &mut Board, source: Point, destination: Point, width: u32) -> Vec<usize> {
+    let dx = (destination.x - source.x).abs();
+    let dy = -(destination.y - source.y).abs();
+    let sx = (destination.x - source.x).signum();
+    let sy = (destination.y - source.y).signum();
+    let mut err = dx + dy;
+    let (mut x, mut y) = (source.x, source.y);
+    let mut corridor = Vec::new();
+
+    while x != destination.x || y != destination.y {
+        let e2 = 2 * err;
+        let step_x = e2 >= dy;
+        let step_y = e2 <= dx;
+        if step_x {
+            err += dy;
+            x += sx;
+        }
+        if step_y {
+            err += dx;
+            y += sy;
+        }
+        if step_x && step_y {
+            // Fill in the corner so the corridor is connected without diagonal moves.
+            carve(board, x - sx, y, width, &mut corridor);
+        }
+        carve(board, x, y, width, &mut corridor);
+    }
+    corridor
+}
+
+fn draw_drunk(
+    board: &mut Board,
+    source: Point,
+    destination: Point,
+    width: u32,
+    rng: &mut PRng,
+) -> Vec<usize> {
+    let max_steps = 4 * (source.dist_manhattan(destination) + 10);
+    let mut p = source;
+    let mut corridor = Vec::new();
+
+    for _ in 0..max_steps {
+        if p == destination { break; }
+
+        let dir = if rng.gen_ratio(DRUNK_TOWARDS_CHANCE, 100) {
+            let delta = destination - p;
+            if delta.x != 0 && (delta.y == 0 || rng.gen::<bool>()) {
+                Point::new(delta.x.signum(), 0)
+            } else {
+                Point::new(0, delta.y.signum())
+            }
+        } else {
+            *rng.choose(&Point::CARDINALS).unwrap()
+        };
+
+        let next = p + dir;
+        if next.x < 1 || next.y < 1 || next.x > board.width as i32 - 2 || next.y > board.height as i32 - 2 {
+            continue;
         }
+        p = next;
+        carve(board, p.x, p.y, width, &mut corridor);
+    }
+
+    // Sober up if the walk ran out of steps.
+    if p != destination {
+        corridor.extend(draw_dogleg(board, p, destination, rng.gen::<bool>(), width));
     }
     corridor
 }
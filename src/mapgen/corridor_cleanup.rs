@@ -0,0 +1,195 @@
+use crate::{random::PRng, point::Point, board::{Board, components::Tile}};
+
+use super::{MetaBuilder, BuildData, MapGenError, common};
+
+/// What [CorridorCleanup] does with dead ends.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub enum DeadEnds {
+    Keep,
+    /// Fill every dead end back in, all the way to the next junction or room.
+    Fill,
+    /// Shorten every dead end by up to this many tiles.
+    Shorten(u32),
+}
+
+/// The eight neighbours of a tile, in order around it.
+const RING: [Point; 8] = [
+    Point::NORTH, Point::NORTH_EAST, Point::EAST, Point::SOUTH_EAST,
+    Point::SOUTH, Point::SOUTH_WEST, Point::WEST, Point::NORTH_WEST,
+];
+
+/// Tidies up the passages between rooms (or all open tiles if there are no rooms):
+///
+/// * thins out corridor tiles that run right next to a room or another corridor,
+/// * fills in or shortens dead ends, see [DeadEnds],
+/// * optionally widens the remaining corridors to `width`.
+///
+/// A tile is only ever filled in if its open neighbours stay connected without it,
+/// so the map never falls apart. The starting position, the exit and the stairs are
+/// left alone. `corridors` are updated to match, and `distances` are recomputed.
+pub(super) struct CorridorCleanup {
+    dead_ends: DeadEnds,
+    thin: bool,
+    width: u32,
+}
+
+impl CorridorCleanup {
+    pub fn new() -> Box<Self> {
+        Self::with_options(DeadEnds::Fill, true, 1)
+    }
+
+    pub fn with_options(dead_ends: DeadEnds, thin: bool, width: u32) -> Box<Self> {
+        assert!(width > 0);
+        Box::new(Self { dead_ends, thin, width })
+    }
+
+    fn is_open(board: &Board, p: Point) -> bool {
+        board.in_bounds_xy(p.x, p.y) && board.get_tile_xy(p.x as u32, p.y as u32) != Tile::Wall
+    }
+
+    /// Number of open tiles directly north, east, south and west of `p`.
+    fn open_cardinals(board: &Board, p: Point) -> usize {
+        Point::CARDINALS.iter().filter(|d| Self::is_open(board, p + **d)).count()
+    }
+
+    /// Whether the open neighbours of `p` form a single unbroken run around it, i.e.
+    /// they stay connected (using cardinal moves) if `p` is filled in.
+    fn is_removable(board: &Board, p: Point) -> bool {
+        let open = RING.map(|d| Self::is_open(board, p + d));
+        let runs = (0..8).filter(|i| open[*i] && !open[(i + 7) % 8]).count();
+        runs == 1
+    }
+
+    /// Fills in the candidate tiles that pass `should_fill`, one layer per call.
+    /// Returns the filled tile indices.
+    fn fill_pass(
+        board: &mut Board,
+        candidates: &[usize],
+        should_fill: impl Fn(&Board, Point) -> bool,
+    ) -> Vec<usize> {
+        let todo = candidates.iter()
+            .copied()
+            .filter(|i| board.get_tile(*i) != Tile::Wall)
+            .filter(|i| should_fill(board, board.index_to_xy(*i).into()))
+            .collect::<Vec<_>>();
+
+        let mut filled = Vec::new();
+        for i in todo {
+            // Earlier fills in this pass may have changed the neighbourhood.
+            if should_fill(board, board.index_to_xy(i).into()) {
+                board.set_tile(i, Tile::Wall);
+                filled.push(i);
+            }
+        }
+        filled
+    }
+
+    /// Grows every corridor tile into a `width` by `width` square towards positive x
+    /// and y, like the corridors of [common::carve_corridor]. Stays away from doors so
+    /// they still block the way.
+    fn widen(&self, board: &mut Board, corridor: &[usize], in_room: &[bool]) -> Vec<usize> {
+        let mut added = Vec::new();
+        for i in corridor {
+            if board.get_tile(*i) != Tile::Floor { continue; }
+            let p: Point = board.index_to_xy(*i).into();
+            for dy in 0..self.width as i32 {
+                for dx in 0..self.width as i32 {
+                    let q = p + Point::new(dx, dy);
+                    if q.x < 1 || q.y < 1 || q.x >= board.width as i32 - 1 || q.y >= board.height as i32 - 1 { continue; }
+                    let j = board.xy_to_index(q.x as u32, q.y as u32);
+                    if in_room[j] || board.get_tile(j) != Tile::Wall { continue; }
+                    let near_door = Point::OCTANT.iter()
+                        .map(|d| q + *d)
+                        .any(|n| matches!(board.get_tile_xy(n.x as u32, n.y as u32), Tile::DoorOpen | Tile::DoorClosed));
+                    if near_door { continue; }
+                    board.set_tile(j, Tile::Floor);
+                    added.push(j);
+                }
+            }
+        }
+        added
+    }
+}
+
+impl MetaBuilder for CorridorCleanup {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let size = (build_data.board.width * build_data.board.height) as usize;
+
+        let mut in_room = vec![false; size];
+        for room in build_data.rects.iter().flatten() {
+            for (x, y) in room.iter_xy() {
+                in_room[build_data.board.xy_to_index(x, y)] = true;
+            }
+        }
+        let protected = [build_data.starting_position, build_data.exit_position];
+        let is_protected = |board: &Board, p: Point| {
+            protected.contains(&Some(p)) || board.get_tile_xy(p.x as u32, p.y as u32) == Tile::DownStairs
+        };
+
+        let outside_rooms = (0..size).filter(|i| !in_room[*i]).collect::<Vec<_>>();
+        let corridor_tiles = match &build_data.corridors {
+            Some(corridors) => {
+                let mut tiles = corridors.iter().flatten().copied().filter(|i| !in_room[*i]).collect::<Vec<_>>();
+                tiles.sort();
+                tiles.dedup();
+                tiles
+            },
+            None => outside_rooms.clone(),
+        };
+
+        let board = &mut build_data.board;
+        let mut filled = Vec::new();
+
+        if self.thin {
+            loop {
+                let pass = Self::fill_pass(board, &corridor_tiles, |board, p| {
+                    board.get_tile_xy(p.x as u32, p.y as u32) == Tile::Floor
+                        && !is_protected(board, p)
+                        && Self::open_cardinals(board, p) >= 2
+                        && Self::is_removable(board, p)
+                });
+                if pass.is_empty() { break; }
+                filled.extend(pass);
+            }
+        }
+
+        let passes = match self.dead_ends {
+            DeadEnds::Keep => 0,
+            DeadEnds::Fill => u32::MAX,
+            DeadEnds::Shorten(n) => n,
+        };
+        for _ in 0..passes {
+            let pass = Self::fill_pass(board, &outside_rooms, |board, p| {
+                !is_protected(board, p)
+                    && Self::open_cardinals(board, p) <= 1
+                    && Self::is_removable(board, p)
+            });
+            if pass.is_empty() { break; }
+            filled.extend(pass);
+        }
+
+        let mut is_filled = vec![false; size];
+        filled.iter().for_each(|i| is_filled[*i] = true);
+
+        if let Some(corridors) = build_data.corridors.as_mut() {
+            corridors.iter_mut().for_each(|c| c.retain(|i| !is_filled[*i]));
+            if self.width > 1 {
+                for corridor in corridors.iter_mut() {
+                    let added = self.widen(board, corridor, &in_room);
+                    corridor.extend(added);
+                }
+            }
+            corridors.retain(|c| !c.is_empty());
+        } else if self.width > 1 {
+            self.widen(board, &corridor_tiles, &in_room);
+        }
+
+        if build_data.distances.is_some() {
+            build_data.distances = build_data.starting_position.map(|s| common::distance_map(board, s));
+        }
+
+        build_data.take_snapshot();
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{random::PRng, board::components::Tile};
+
+use super::{MetaBuilder, BuildData, MapGenError, common};
+
+/// Turns every tile that can't be reached from the starting position into a wall.
+pub(super) struct CullUnreachable {}
+
+impl CullUnreachable {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
+
+impl MetaBuilder for CullUnreachable {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let Some(start) = build_data.starting_position else {
+            return Err(MapGenError::MissingData {
+                builder: std::any::type_name::<Self>(),
+                field: "starting_position",
+            });
+        };
+
+        let distances = common::distance_map(&build_data.board, start);
+        for (i, d) in distances.iter().enumerate() {
+            if d.is_none() {
+                build_data.board.set_tile(i, Tile::Wall);
+            }
+        }
+        build_data.take_snapshot();
+        Ok(())
+    }
+}
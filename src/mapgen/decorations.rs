@@ -0,0 +1,32 @@
+use crate::{random::PRng, board::components::Tile, point::Point};
+
+use super::{MetaBuilder, BuildData, MapGenError};
+
+/// Rolls the decorative features of a [Theme](crate::theme::Theme) onto the floor
+/// tiles, leaving the starting position bare. Every floor tile gets the first feature
+/// whose chance roll succeeds. Decorations only change how a tile is drawn.
+pub(super) struct Decorations {
+    chances: Vec<f64>,
+}
+
+impl Decorations {
+    pub fn new(chances: Vec<f64>) -> Box<Self> {
+        Box::new(Self { chances })
+    }
+}
+
+impl MetaBuilder for Decorations {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let board = &build_data.board;
+        let mut decorations = vec![None; (board.width * board.height) as usize];
+
+        for (i, decoration) in decorations.iter_mut().enumerate() {
+            if board.get_tile(i) != Tile::Floor { continue; }
+            if build_data.starting_position == Some(Point::from(board.index_to_xy(i))) { continue; }
+            *decoration = self.chances.iter().position(|chance| rng.gen_bool(*chance));
+        }
+
+        build_data.decorations = Some(decorations);
+        Ok(())
+    }
+}
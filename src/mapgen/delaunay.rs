@@ -0,0 +1,79 @@
+use crate::point::Point;
+
+/// Delaunay triangulation of `points` (Bowyer-Watson). Returns the edges of the
+/// triangulation as pairs of indices into `points`, with the lower index first.
+/// Fewer than three points, or points that are all on a line, give no triangles;
+/// in that case the returned list may not connect every point.
+pub fn triangulate(points: &[Point]) -> Vec<(usize, usize)> {
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    if n == 2 {
+        return vec![(0, 1)];
+    }
+
+    let mut vertices: Vec<(f64, f64)> = points.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+
+    // Super triangle that contains every point.
+    let min_x = vertices.iter().map(|v| v.0).fold(f64::INFINITY, f64::min);
+    let min_y = vertices.iter().map(|v| v.1).fold(f64::INFINITY, f64::min);
+    let max_x = vertices.iter().map(|v| v.0).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = vertices.iter().map(|v| v.1).fold(f64::NEG_INFINITY, f64::max);
+    let d = f64::max(max_x - min_x, max_y - min_y) * 20. + 10.;
+    let (mid_x, mid_y) = ((min_x + max_x) / 2., (min_y + max_y) / 2.);
+    vertices.push((mid_x - 2. * d, mid_y - d));
+    vertices.push((mid_x, mid_y + 2. * d));
+    vertices.push((mid_x + 2. * d, mid_y - d));
+
+    let mut triangles: Vec<[usize; 3]> = vec![[n, n + 1, n + 2]];
+
+    for i in 0..n {
+        // Skip duplicate points, they would only produce degenerate triangles.
+        if points[..i].contains(&points[i]) {
+            continue;
+        }
+
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .into_iter()
+            .partition(|t| in_circumcircle(&vertices, t, vertices[i]));
+        triangles = good;
+
+        // The boundary of the hole left by the bad triangles is made up of the edges
+        // that belong to exactly one bad triangle.
+        let edges = bad.iter()
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .collect::<Vec<_>>();
+        for &(a, b) in edges.iter() {
+            let shared = edges.iter()
+                .filter(|&&(c, d)| (c == a && d == b) || (c == b && d == a))
+                .count() > 1;
+            if !shared {
+                triangles.push([a, b, i]);
+            }
+        }
+    }
+
+    let mut result = triangles.iter()
+        .filter(|t| t.iter().all(|&v| v < n))
+        .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        .map(|(a, b)| (usize::min(a, b), usize::max(a, b)))
+        .collect::<Vec<_>>();
+    result.sort();
+    result.dedup();
+    result
+}
+
+fn in_circumcircle(vertices: &[(f64, f64)], t: &[usize; 3], p: (f64, f64)) -> bool {
+    let (a, b, c) = (vertices[t[0]], vertices[t[1]], vertices[t[2]]);
+    let orientation = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+
+    let (adx, ady) = (a.0 - p.0, a.1 - p.1);
+    let (bdx, bdy) = (b.0 - p.0, b.1 - p.1);
+    let (cdx, cdy) = (c.0 - p.0, c.1 - p.1);
+    let det = (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+        - (bdx * bdx + bdy * bdy) * (adx * cdy - cdx * ady)
+        + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady);
+
+    if orientation > 0. { det > 0. } else { det < 0. }
+}
@@ -0,0 +1,47 @@
+use bevy::prelude::debug;
+
+use crate::{random::PRng, board::components::Tile};
+
+use super::{MetaBuilder, BuildData, MapGenError, common};
+
+/// Places the [Tile::DownStairs] on the floor tile farthest away from the starting
+/// position, and stores the distance map in [BuildData]. Requires a starting position.
+pub(super) struct DistantExit {}
+
+impl DistantExit {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {})
+    }
+}
+
+impl MetaBuilder for DistantExit {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let Some(start) = build_data.starting_position else {
+            return Err(MapGenError::MissingData {
+                builder: std::any::type_name::<Self>(),
+                field: "starting_position",
+            });
+        };
+
+        let distances = common::distance_map(&build_data.board, start);
+
+        // Ties are broken by the lowest index, which keeps the result deterministic.
+        let farthest = distances.iter()
+            .enumerate()
+            .filter(|(i, _)| build_data.board.get_tile(*i) == Tile::Floor)
+            .filter_map(|(i, d)| d.map(|d| (i, d)))
+            .filter(|&(_, d)| d > 0)
+            .max_by_key(|&(i, d)| (d, std::cmp::Reverse(i)))
+            .map(|(i, _)| i);
+
+        if let Some(i) = farthest {
+            build_data.board.set_tile(i, Tile::DownStairs);
+            build_data.exit_position = Some(build_data.board.index_to_xy(i).into());
+            debug!("build_data exit position set");
+        }
+
+        build_data.distances = Some(distances);
+        build_data.take_snapshot();
+        Ok(())
+    }
+}
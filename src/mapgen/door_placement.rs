@@ -0,0 +1,73 @@
+use crate::{random::PRng, rect::Rect, point::Point, board::{Board, components::Tile}};
+
+use super::{MetaBuilder, BuildData, MapGenError};
+
+/// Places doors where the corridors in [BuildData] enter a room. A door is either
+/// open or closed, decided by `open_chance`. Requires both `rects` and `corridors`.
+pub(super) struct DoorPlacement {
+    open_chance: f64,
+}
+
+impl DoorPlacement {
+    pub fn new(open_chance: f64) -> Box<Self> {
+        Box::new(Self { open_chance })
+    }
+
+    fn is_wall(board: &Board, p: Point) -> bool {
+        !board.in_bounds_xy(p.x, p.y) || board.get_tile_xy(p.x as u32, p.y as u32) == Tile::Wall
+    }
+
+    /// A door fits at `p` if it sits in a one tile wide passage with walls on both sides,
+    /// a room on one end and the corridor on the other. Spots where the corridor turns
+    /// right outside the door (a diagonal entry) are rejected.
+    fn door_possible(board: &Board, rooms: &[Rect], p: Point) -> bool {
+        if board.get_tile_xy(p.x as u32, p.y as u32) != Tile::Floor { return false; }
+        if rooms.iter().any(|r| r.contains(p)) { return false; }
+
+        for (along, side) in [(Point::EAST, Point::NORTH), (Point::NORTH, Point::EAST)] {
+            if !Self::is_wall(board, p + side) || !Self::is_wall(board, p - side) { continue; }
+            if !board.is_walkable(p + along) || !board.is_walkable(p - along) { continue; }
+
+            let room_ahead = rooms.iter().any(|r| r.contains(p + along));
+            let room_behind = rooms.iter().any(|r| r.contains(p - along));
+            let outside = match (room_ahead, room_behind) {
+                (true, false) => p - along,
+                (false, true) => p + along,
+                _ => continue,
+            };
+
+            if Self::is_wall(board, outside + side) && Self::is_wall(board, outside - side) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl MetaBuilder for DoorPlacement {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let Some(rooms) = build_data.rects.clone() else {
+            return Err(MapGenError::MissingData { builder: std::any::type_name::<Self>(), field: "rects" });
+        };
+        let Some(corridors) = build_data.corridors.clone() else {
+            return Err(MapGenError::MissingData { builder: std::any::type_name::<Self>(), field: "corridors" });
+        };
+
+        let mut doors: Vec<Point> = Vec::new();
+
+        for i in corridors.iter().flatten() {
+            let p: Point = build_data.board.index_to_xy(*i).into();
+
+            // Never put two doors next to each other.
+            if doors.iter().any(|d| d.dist_chebyshev(p) <= 1) { continue; }
+            if !Self::door_possible(&build_data.board, &rooms, p) { continue; }
+
+            let tile = if rng.gen_bool(self.open_chance) { Tile::DoorOpen } else { Tile::DoorClosed };
+            build_data.board.set_tile(*i, tile);
+            doors.push(p);
+        }
+
+        build_data.take_snapshot();
+        Ok(())
+    }
+}
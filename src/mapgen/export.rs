@@ -0,0 +1,93 @@
+use image::{Rgb, RgbImage};
+
+use crate::{board::components::Tile, point::Point};
+
+use super::BuildData;
+
+const START_COLOR: Rgb<u8> = Rgb([230, 60, 60]);
+const SHEET_BACKGROUND: Rgb<u8> = Rgb([40, 40, 40]);
+
+pub(super) fn tile_char(tile: Tile) -> char {
+    match tile {
+        Tile::Floor => '.',
+        Tile::Wall => '#',
+        Tile::DoorOpen => '\'',
+        Tile::DoorClosed => '+',
+        Tile::DownStairs => '>',
+    }
+}
+
+pub(super) fn tile_color(tile: Tile) -> Rgb<u8> {
+    match tile {
+        Tile::Floor => Rgb([200, 200, 170]),
+        Tile::Wall => Rgb([60, 60, 20]),
+        Tile::DoorOpen => Rgb([190, 130, 60]),
+        Tile::DoorClosed => Rgb([140, 80, 20]),
+        Tile::DownStairs => Rgb([60, 120, 230]),
+    }
+}
+
+/// Renders the board as text, one character per tile, with the starting position as `@`.
+/// The first line is the top row of the board (highest y).
+pub fn to_ascii(build_data: &BuildData) -> String {
+    let board = &build_data.board;
+    let mut result = String::with_capacity(((board.width + 1) * board.height) as usize);
+
+    for y in (0..board.height).rev() {
+        for x in 0..board.width {
+            if build_data.starting_position == Some(Point::from((x, y))) {
+                result.push('@');
+            } else {
+                result.push(tile_char(board.get_tile_xy(x, y)));
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Renders the board as an image with one pixel per tile. The top row of the image is
+/// the top row of the board (highest y).
+pub fn to_image(build_data: &BuildData) -> RgbImage {
+    let board = &build_data.board;
+    let mut image = RgbImage::new(board.width, board.height);
+
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let color = if build_data.starting_position == Some(Point::from((x, y))) {
+                START_COLOR
+            } else {
+                tile_color(board.get_tile_xy(x, y))
+            };
+            image.put_pixel(x, board.height - 1 - y, color);
+        }
+    }
+    image
+}
+
+/// Lays out the images of several maps in a grid with `columns` columns, separated
+/// by a one pixel border.
+pub fn contact_sheet(maps: &[BuildData], columns: u32) -> RgbImage {
+    assert!(columns > 0);
+    let cell_width = maps.iter().map(|m| m.board.width).max().unwrap_or(0) + 1;
+    let cell_height = maps.iter().map(|m| m.board.height).max().unwrap_or(0) + 1;
+    let rows = (maps.len() as u32).div_ceil(columns);
+
+    let mut sheet = RgbImage::from_pixel(
+        columns * cell_width + 1,
+        rows * cell_height + 1,
+        SHEET_BACKGROUND,
+    );
+
+    for (i, map) in maps.iter().enumerate() {
+        let (col, row) = (i as u32 % columns, i as u32 / columns);
+        let image = to_image(map);
+        image::imageops::replace(
+            &mut sheet,
+            &image,
+            (col * cell_width + 1) as i64,
+            (row * cell_height + 1) as i64,
+        );
+    }
+    sheet
+}
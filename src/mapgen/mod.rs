@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use bevy::prelude::*;
 
 mod simple_rooms;
@@ -7,8 +9,27 @@ mod room_start_pos;
 mod bsp;
 mod bsp_interior;
 mod cellular_automata;
+mod door_placement;
+mod delaunay;
+mod room_shapes;
+mod distant_exit;
+mod area_start_pos;
+mod cull_unreachable;
+mod decorations;
+mod symmetry;
+mod corridor_cleanup;
+pub mod export;
+pub mod analysis;
+pub mod room_graph;
+pub mod prefab;
+pub mod tiled;
+pub mod cli;
+mod progress;
+pub mod systems;
+
+pub use progress::GenProgress;
 
-use crate::{point::Point, random::{self, PRngBuilder}, config, board::{Board, components::Tile}, rect::Rect, state::MainState};
+use crate::{theme::{Theme, Themes}, point::Point, random::{self, RngStream}, config, board::{Board, components::Tile}, rect::Rect, state::MainState};
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum MapGenSet {
@@ -21,14 +42,35 @@ pub struct MapGenPlugin;
 impl Plugin for MapGenPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BuildData>()
+            .init_resource::<Themes>()
             .configure_set(
                 OnEnter(MainState::Game), 
                 MapGenSet::Generation.before(MapGenSet::Spawning)
-            );
+            )
+            .add_systems(OnEnter(MainState::Generating), systems::start_generation)
+            .add_systems(Update, systems::poll_generation.run_if(in_state(MainState::Generating)));
     }
 }
 
+#[allow(dead_code)]
 pub fn random_builder(seed: u64, depth: u32) -> BuildData {
+    random_chain(seed, depth).build()
+}
+
+/// Runs the builder chain of `theme` and rolls its decorations, reporting to
+/// `progress` while it runs if given.
+pub fn themed_builder(theme: &Theme, seed: u64, depth: u32, progress: Option<Arc<GenProgress>>) -> BuildData {
+    // Theme builders are checked when the themes are loaded.
+    let mut builder = named_chain(&theme.builder, seed, depth)
+        .unwrap()
+        .with(decorations::Decorations::new(theme.features.iter().map(|f| f.chance).collect()));
+    if let Some(progress) = progress {
+        builder = builder.with_progress(progress);
+    }
+    builder.build()
+}
+
+fn random_chain(seed: u64, depth: u32) -> MapBuilder {
     MapBuilder::new(
         depth, 
         seed, 
@@ -36,34 +78,175 @@ pub fn random_builder(seed: u64, depth: u32) -> BuildData {
         config::map::MAP_TILE_HEIGHT
     )
         .with_starter(simple_rooms::SimpleRoomBuilder::new())
+        .with(room_shapes::RoomShapes::new())
         .with(room_corridors::RoomCorridors::new())
+        .with(corridor_cleanup::CorridorCleanup::new())
+        .with(door_placement::DoorPlacement::new(config::map::DOOR_OPEN_CHANCE))
         .with(room_start_pos::RoomBasedStartingPosition::new())
-        .build()
+        .with(distant_exit::DistantExit::new())
+}
+
+/// Names accepted by [named_builder].
+pub const BUILDER_NAMES: [&str; 9] = [
+    "random",
+    "simple_rooms",
+    "bsp",
+    "bsp_interior",
+    "bsp_mst",
+    "delaunay_drunk",
+    "cellular_automata",
+    "temple",
+    "arena",
+];
+
+/// Runs the builder chain with the given name (see [BUILDER_NAMES]), or returns `None`
+/// if there is no such chain.
+pub fn named_builder(name: &str, seed: u64, depth: u32) -> Option<BuildData> {
+    named_chain(name, seed, depth).map(|mut builder| builder.build())
+}
+
+/// Loads a hand-made map: a Tiled map if the extension says so (see
+/// [tiled::is_tiled_path]), a [prefab] otherwise.
+pub fn load_map(path: &std::path::Path, depth: u32) -> Result<BuildData, String> {
+    let result = if tiled::is_tiled_path(path) {
+        tiled::load(path, depth).map_err(|e| e.to_string())
+    } else {
+        prefab::load(path, depth).map_err(|e| e.to_string())
+    };
+    result.map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn named_chain(name: &str, seed: u64, depth: u32) -> Option<MapBuilder> {
+    let builder = MapBuilder::new(
+        depth,
+        seed,
+        config::map::MAP_TILE_WIDTH,
+        config::map::MAP_TILE_HEIGHT
+    );
+
+    let builder = match name {
+        "random" => random_chain(seed, depth),
+        "simple_rooms" => builder
+            .with_starter(simple_rooms::SimpleRoomBuilder::new())
+            .with(room_corridors::RoomCorridors::new())
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "bsp" => builder
+            .with_starter(bsp::BspMapBuilder::new())
+            .with(corridor_cleanup::CorridorCleanup::new())
+            .with(door_placement::DoorPlacement::new(config::map::DOOR_OPEN_CHANCE))
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "bsp_interior" => builder
+            .with_starter(bsp_interior::BspInteriorBuilder::new())
+            .with(door_placement::DoorPlacement::new(config::map::DOOR_OPEN_CHANCE))
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "bsp_mst" => builder
+            .with_starter(bsp::BspMapBuilder::rooms_only())
+            .with(room_corridors::RoomCorridors::with_strategy(
+                room_corridors::RoomLinking::SpanningTree { extra_edges: 3 },
+                common::CorridorShape::Straight,
+                1,
+            ))
+            .with(door_placement::DoorPlacement::new(config::map::DOOR_OPEN_CHANCE))
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "delaunay_drunk" => builder
+            .with_starter(simple_rooms::SimpleRoomBuilder::new())
+            .with(room_shapes::RoomShapes::new())
+            .with(room_corridors::RoomCorridors::with_strategy(
+                room_corridors::RoomLinking::Delaunay,
+                common::CorridorShape::Drunk,
+                1,
+            ))
+            .with(corridor_cleanup::CorridorCleanup::new())
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "cellular_automata" => builder
+            .with_starter(cellular_automata::CellularAutomataBuilder::new())
+            .with(area_start_pos::AreaStartingPosition::new())
+            .with(cull_unreachable::CullUnreachable::new())
+            .with(distant_exit::DistantExit::new()),
+        "temple" => builder
+            .with_starter(bsp::BspMapBuilder::rooms_only())
+            .with(room_corridors::RoomCorridors::with_strategy(
+                room_corridors::RoomLinking::SpanningTree { extra_edges: 2 },
+                common::CorridorShape::Straight,
+                1,
+            ))
+            .with(symmetry::Symmetry::new(symmetry::SymmetryMode::Both))
+            .with(door_placement::DoorPlacement::new(config::map::DOOR_OPEN_CHANCE))
+            .with(room_start_pos::RoomBasedStartingPosition::new())
+            .with(distant_exit::DistantExit::new()),
+        "arena" => builder
+            .with_starter(cellular_automata::CellularAutomataBuilder::new())
+            .with(symmetry::Symmetry::new(symmetry::SymmetryMode::Rotate180))
+            .with(area_start_pos::AreaStartingPosition::new())
+            .with(cull_unreachable::CullUnreachable::new())
+            .with(distant_exit::DistantExit::new()),
+        _ => return None,
+    };
+    Some(builder)
 }
 
 #[derive(Debug, Resource, Clone)]
 pub struct BuildData {
+    pub depth: u32,
     pub board: Board,
     pub starting_position: Option<Point>,
     pub rects: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
+    pub exit_position: Option<Point>,
+    /// Steps from the starting position to each tile, see [common::distance_map].
+    /// Spawners can use this to scale danger and loot with distance from the start.
+    pub distances: Option<Vec<Option<u32>>>,
+    /// How the `rects` are connected, filled in by [MapBuilder] once the chain is done.
+    pub room_graph: Option<room_graph::RoomGraph>,
+    /// Index of the [Theme] feature drawn on each tile, if any.
+    pub decorations: Option<Vec<Option<usize>>>,
+    /// Monsters placed by hand, e.g. in a [prefab]. If set, they are spawned instead
+    /// of rolling on the spawn table.
+    pub spawns: Option<Vec<(Point, String)>>,
     pub history: Vec<Vec<Tile>>,
+    progress: Option<Arc<GenProgress>>,
 }
 
 impl Default for BuildData {
     fn default() -> Self {
+        Self::new(0, 2, 2)
+    }
+}
+
+impl BuildData {
+    fn new(depth: u32, width: u32, height: u32) -> Self {
         Self {
-            board: Board::new(0, 2, 2),
+            depth,
+            board: Board::new(depth, width, height),
             starting_position: None,
             rects: None,
             corridors: None,
+            exit_position: None,
+            distances: None,
+            room_graph: None,
+            decorations: None,
+            spawns: None,
             history: Vec::new(),
+            progress: None,
         }
     }
-}
 
-impl BuildData {
+    fn build_room_graph(&mut self) {
+        self.room_graph = self.rects.as_ref().map(|rooms| {
+            let corridors = self.corridors.as_deref().unwrap_or_default();
+            room_graph::RoomGraph::new(&self.board, rooms, corridors)
+        });
+    }
+
     fn take_snapshot(&mut self) {
+        if let Some(progress) = &self.progress {
+            progress.add_snapshot();
+        }
         if config::SHOW_MAP_GEN {
             let ss = self.board.get_tiles_cloned();
             self.history.push(ss);
@@ -71,12 +254,73 @@ impl BuildData {
     }
 }
 
+/// Reasons a builder chain can fail. A failed chain is retried by [MapBuilder::build].
+#[derive(Debug, Clone)]
+pub enum MapGenError {
+    /// More than one [InitBuilder] was added to the chain.
+    MultipleStarters,
+    /// The chain has no [InitBuilder].
+    MissingStarter,
+    /// A builder needs a [BuildData] field that no earlier builder filled in.
+    MissingData { builder: &'static str, field: &'static str },
+    /// A builder needs at least one room, but there are none.
+    NoRooms { builder: &'static str },
+    /// A builder needs at least one floor tile, but there are none.
+    NoFloor { builder: &'static str },
+    /// The board is too small for the builder to work with.
+    BoardTooSmall { builder: &'static str, width: u32, height: u32 },
+    /// The finished map is not playable, see [analysis::validate].
+    Invalid(Vec<analysis::Violation>),
+}
+
+impl std::fmt::Display for MapGenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapGenError::MultipleStarters => write!(f, "only one starting builder is allowed"),
+            MapGenError::MissingStarter => write!(f, "builder chain has no starting builder"),
+            MapGenError::MissingData { builder, field } => {
+                write!(f, "{builder} requires that BuildData {field} is not None")
+            },
+            MapGenError::NoRooms { builder } => write!(f, "{builder} requires at least one room"),
+            MapGenError::NoFloor { builder } => write!(f, "{builder} requires at least one floor tile"),
+            MapGenError::BoardTooSmall { builder, width, height } => {
+                write!(f, "board of {width}x{height} is too small for {builder}")
+            },
+            MapGenError::Invalid(violations) => {
+                write!(f, "map failed validation: ")?;
+                for (i, v) in violations.iter().enumerate() {
+                    if i > 0 { write!(f, "; ")?; }
+                    write!(f, "{v}")?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for MapGenError {}
+
 trait InitBuilder {
-    fn build(&mut self, rng: &mut random::PRng, build_data: &mut BuildData);
+    fn build(&mut self, rng: &mut random::PRng, build_data: &mut BuildData) -> Result<(), MapGenError>;
+
+    /// Name shown while the builder runs, the type name by default.
+    fn name(&self) -> &'static str {
+        short_type_name::<Self>()
+    }
 }
 
 trait MetaBuilder {
-    fn build(&mut self, rng: &mut random::PRng, build_data: &mut BuildData);
+    fn build(&mut self, rng: &mut random::PRng, build_data: &mut BuildData) -> Result<(), MapGenError>;
+
+    /// Name shown while the builder runs, the type name by default.
+    fn name(&self) -> &'static str {
+        short_type_name::<Self>()
+    }
+}
+
+fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
 }
 
 struct MapBuilder {
@@ -84,6 +328,9 @@ struct MapBuilder {
     depth: u32,
     starter: Option<Box<dyn InitBuilder>>,
     chainers: Vec<Box<dyn MetaBuilder>>,
+    /// Set when the chain itself is malformed, reported by [MapBuilder::try_build].
+    chain_error: Option<MapGenError>,
+    progress: Option<Arc<GenProgress>>,
     pub build_data: BuildData,
 }
 
@@ -94,20 +341,16 @@ impl MapBuilder {
             seed,
             starter: None,
             chainers: Vec::new(),
-            build_data: BuildData {
-                board: Board::new(depth, width, height),
-                starting_position: None,
-                rects: None,
-                corridors: None,
-                history: Vec::new(),
-            }
+            chain_error: None,
+            progress: None,
+            build_data: BuildData::new(depth, width, height),
         }
     }
 
     fn with_starter(mut self, starter: Box<dyn InitBuilder>) -> Self {
         match self.starter {
             None => self.starter = Some(starter),
-            Some(_) => panic!("You can only have one starting builder."),
+            Some(_) => self.chain_error = Some(MapGenError::MultipleStarters),
         };
         self
     }
@@ -117,23 +360,93 @@ impl MapBuilder {
         self
     }
 
+    fn with_progress(mut self, progress: Arc<GenProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Runs the chain once with the given attempt number. Attempt 0 uses the plain
+    /// seed, later attempts derive a sub-seed from it.
+    fn try_build(&mut self, attempt: u32) -> Result<BuildData, MapGenError> {
+        if let Some(ref e) = self.chain_error {
+            return Err(e.clone());
+        }
+
+        let mut prng_builder = RngStream::MapGen.builder(self.seed, self.depth);
+        if attempt > 0 {
+            prng_builder = prng_builder.write_u32(attempt);
+        }
+        let mut prng = prng_builder.build();
+
+        self.build_data = BuildData::new(self.depth, self.build_data.board.width, self.build_data.board.height);
+        self.build_data.progress = self.progress.clone();
+        let progress = self.progress.as_deref();
+        if let Some(progress) = progress {
+            progress.start_attempt(attempt, 1 + self.chainers.len() as u32);
+        }
+
+        let Some(starter) = &mut self.starter else {
+            return Err(MapGenError::MissingStarter);
+        };
+        if let Some(progress) = progress {
+            progress.start_stage(starter.name());
+        }
+        starter.build(&mut prng, &mut self.build_data)?;
+
+        for builder in self.chainers.iter_mut() {
+            if let Some(progress) = progress {
+                progress.start_stage(builder.name());
+            }
+            builder.build(&mut prng, &mut self.build_data)?;
+        }
+        self.build_data.progress = None;
+        self.build_data.build_room_graph();
+
+        let violations = analysis::validate(&self.build_data);
+        if !violations.is_empty() {
+            return Err(MapGenError::Invalid(violations));
+        }
+
+        Ok(self.build_data.clone())
+    }
+
+    /// Runs the chain, retrying up to [MAP_GEN_ATTEMPTS](config::map::MAP_GEN_ATTEMPTS)
+    /// times with derived seeds before falling back to [fallback_builder].
     fn build(&mut self) -> BuildData {
-        let mut prng = PRngBuilder::new_seeded(config::MAP_GENERATION_SEED)
-            .write_u32(self.depth)
-            .write_u64(self.seed)
-            .build();
-
-        match &mut self.starter {
-            None => panic!("Cannot run map builder chain without an initial builder"),
-            Some(starter) => {
-                starter.build(&mut prng, &mut self.build_data);
+        for attempt in 0..config::map::MAP_GEN_ATTEMPTS {
+            match self.try_build(attempt) {
+                Ok(build_data) => return build_data,
+                Err(e) => warn!("map generation (seed {}, attempt {attempt}) failed: {e}", self.seed),
             }
         }
+        fallback_builder(self.seed, self.depth, self.build_data.board.width, self.build_data.board.height)
+    }
+}
 
-        for builder in self.chainers.iter_mut() {
-            builder.build(&mut prng, &mut self.build_data);
+/// A known-good chain used when another chain keeps failing. If even this fails
+/// (e.g. on a tiny board) the result is a single open room.
+fn fallback_builder(seed: u64, depth: u32, width: u32, height: u32) -> BuildData {
+    let mut builder = MapBuilder::new(depth, seed, width, height)
+        .with_starter(simple_rooms::SimpleRoomBuilder::new())
+        .with(room_corridors::RoomCorridors::new())
+        .with(room_start_pos::RoomBasedStartingPosition::new())
+        .with(distant_exit::DistantExit::new());
+
+    for attempt in 0..config::map::MAP_GEN_ATTEMPTS {
+        if let Ok(build_data) = builder.try_build(attempt) {
+            return build_data;
         }
+    }
 
-        self.build_data.clone()
+    error!("fallback map generation failed, using a single room");
+    let mut build_data = BuildData::new(depth, width, height);
+    let room = Rect::new(1, 1, width.saturating_sub(2).max(1), height.saturating_sub(2).max(1));
+    if width > 2 && height > 2 {
+        build_data.board.set_rect(&room, Tile::Floor);
     }
+    build_data.starting_position = Some(room.center());
+    build_data.rects = Some(vec![room]);
+    build_data.build_room_graph();
+    build_data.take_snapshot();
+    build_data
 }
@@ -0,0 +1,363 @@
+//! Hand-made maps in a plain text format, one character per tile:
+//!
+//! ```text
+//! // Comments start with two slashes.
+//! r Floor Rat
+//! g Floor Goblin
+//! ---
+//! ##########
+//! #@..+..r>#
+//! #...#..g.#
+//! ##########
+//! ```
+//!
+//! Everything above the `---` line is the legend: a character, the [Tile] it stands
+//! for and an optional marker. The marker `start` is the starting position, any other
+//! marker is the name of a monster to spawn there. The legend can be left out (along
+//! with the `---`), and extends the default one: `.` floor, `#` wall, `'` open door,
+//! `+` closed door, `>` down stairs and `@` floor with the starting position.
+//!
+//! The first map line is the top row of the board (highest y), like [to_ascii](super::export::to_ascii).
+
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use crate::{board::{Board, components::Tile}, point::Point, random::PRng, rect::Rect};
+
+use super::{BuildData, InitBuilder, MapBuilder, MapGenError, area_start_pos, common, export};
+
+const SEPARATOR: &str = "---";
+const COMMENT: &str = "//";
+const START_MARKER: &str = "start";
+
+pub(super) const TILE_NAMES: [(Tile, &str); 5] = [
+    (Tile::Floor, "Floor"),
+    (Tile::Wall, "Wall"),
+    (Tile::DoorOpen, "DoorOpen"),
+    (Tile::DoorClosed, "DoorClosed"),
+    (Tile::DownStairs, "DownStairs"),
+];
+
+/// Reasons a prefab can't be loaded. Line numbers start at 1.
+#[derive(Debug)]
+pub enum PrefabError {
+    Io(String),
+    /// There are no map lines.
+    Empty,
+    /// A legend line is not `<char> <tile> [marker]`.
+    BadLegend { line: usize },
+    UnknownTile { line: usize, name: String },
+    /// A map character is not in the legend.
+    UnknownChar { line: usize, ch: char },
+    /// A map line is shorter or longer than the first one.
+    RaggedRow { line: usize, expected: usize, found: usize },
+    TooSmall { width: usize, height: usize },
+    MultipleStarts { line: usize },
+    /// The map was read but is not playable.
+    Invalid(MapGenError),
+}
+
+impl Display for PrefabError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrefabError::Io(e) => write!(f, "{e}"),
+            PrefabError::Empty => write!(f, "prefab has no map lines"),
+            PrefabError::BadLegend { line } => write!(f, "line {line}: expected `<char> <tile> [marker]`"),
+            PrefabError::UnknownTile { line, name } => write!(f, "line {line}: unknown tile {name}"),
+            PrefabError::UnknownChar { line, ch } => write!(f, "line {line}: `{ch}` is not in the legend"),
+            PrefabError::RaggedRow { line, expected, found } => {
+                write!(f, "line {line}: expected {expected} tiles, found {found}")
+            },
+            PrefabError::TooSmall { width, height } => write!(f, "map of {width}x{height} is too small"),
+            PrefabError::MultipleStarts { line } => write!(f, "line {line}: second starting position"),
+            PrefabError::Invalid(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for PrefabError {}
+
+#[derive(Debug, Clone)]
+struct LegendEntry {
+    tile: Tile,
+    marker: Option<String>,
+}
+
+/// A parsed prefab, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    pub board: Board,
+    pub starting_position: Option<Point>,
+    pub spawns: Vec<(Point, String)>,
+    /// Rooms, if the source format has them. The text format doesn't.
+    pub rects: Option<Vec<Rect>>,
+}
+
+pub(super) fn tile_from_name(name: &str) -> Option<Tile> {
+    TILE_NAMES.iter().find(|(_, n)| *n == name).map(|(tile, _)| *tile)
+}
+
+pub(super) fn tile_name(tile: Tile) -> &'static str {
+    TILE_NAMES.iter().find(|(t, _)| *t == tile).unwrap().1
+}
+
+fn default_legend() -> BTreeMap<char, LegendEntry> {
+    let mut legend = TILE_NAMES.iter()
+        .map(|(tile, _)| (export::tile_char(*tile), LegendEntry { tile: *tile, marker: None }))
+        .collect::<BTreeMap<_, _>>();
+    legend.insert('@', LegendEntry { tile: Tile::Floor, marker: Some(START_MARKER.to_string()) });
+    legend
+}
+
+fn parse_legend_line(line: usize, text: &str) -> Result<(char, LegendEntry), PrefabError> {
+    let mut chars = text.chars();
+    let ch = chars.next().ok_or(PrefabError::BadLegend { line })?;
+    let rest = chars.as_str();
+    if !rest.starts_with(char::is_whitespace) {
+        return Err(PrefabError::BadLegend { line });
+    }
+
+    let rest = rest.trim();
+    let (name, marker) = match rest.split_once(char::is_whitespace) {
+        Some((name, marker)) => (name, Some(marker.trim().to_string())),
+        None => (rest, None),
+    };
+    let tile = tile_from_name(name)
+        .ok_or_else(|| PrefabError::UnknownTile { line, name: name.to_string() })?;
+    Ok((ch, LegendEntry { tile, marker }))
+}
+
+/// Parses a prefab, see the [module documentation](self).
+pub fn parse(text: &str, depth: u32) -> Result<Prefab, PrefabError> {
+    let lines = text.lines().enumerate().map(|(i, l)| (i + 1, l)).collect::<Vec<_>>();
+    let (header, rows) = match lines.iter().position(|(_, l)| l.trim_end() == SEPARATOR) {
+        Some(i) => (&lines[..i], &lines[i + 1..]),
+        None => (&lines[..0], &lines[..]),
+    };
+
+    let mut legend = default_legend();
+    for (line, text) in header {
+        if text.trim().is_empty() || text.trim_start().starts_with(COMMENT) { continue; }
+        let (ch, entry) = parse_legend_line(*line, text)?;
+        legend.insert(ch, entry);
+    }
+
+    let rows = rows.iter()
+        .map(|(line, text)| (*line, text.trim_end()))
+        .filter(|(_, text)| !text.is_empty())
+        .collect::<Vec<_>>();
+    let Some(&(_, first)) = rows.first() else {
+        return Err(PrefabError::Empty);
+    };
+
+    let width = first.chars().count();
+    let height = rows.len();
+    if width < 2 || height < 2 {
+        return Err(PrefabError::TooSmall { width, height });
+    }
+
+    let mut prefab = Prefab {
+        board: Board::new(depth, width as u32, height as u32),
+        starting_position: None,
+        spawns: Vec::new(),
+        rects: None,
+    };
+
+    for (row, (line, text)) in rows.iter().enumerate() {
+        let found = text.chars().count();
+        if found != width {
+            return Err(PrefabError::RaggedRow { line: *line, expected: width, found });
+        }
+
+        let y = (height - 1 - row) as u32;
+        for (x, ch) in text.chars().enumerate() {
+            let entry = legend.get(&ch).ok_or(PrefabError::UnknownChar { line: *line, ch })?;
+            let p = Point::new(x as i32, y as i32);
+            prefab.board.set_tile_xy(x as u32, y, entry.tile);
+
+            match entry.marker.as_deref() {
+                Some(START_MARKER) => {
+                    if prefab.starting_position.is_some() {
+                        return Err(PrefabError::MultipleStarts { line: *line });
+                    }
+                    prefab.starting_position = Some(p);
+                },
+                Some(name) => prefab.spawns.push((p, name.to_string())),
+                None => {},
+            }
+        }
+    }
+    Ok(prefab)
+}
+
+/// Writes the map as a prefab. Monsters in [BuildData::spawns] get a legend entry
+/// each, using the first letter of their name if it's still free.
+pub fn to_text(build_data: &BuildData) -> String {
+    let board = &build_data.board;
+    let mut legend = default_legend();
+    let mut markers: BTreeMap<Point, char> = BTreeMap::new();
+    let mut header = String::new();
+
+    for (p, name) in build_data.spawns.iter().flatten() {
+        let tile = board.get_tile_xy(p.x as u32, p.y as u32);
+        let existing = legend.iter()
+            .find(|(_, e)| e.tile == tile && e.marker.as_deref() == Some(name.as_str()))
+            .map(|(ch, _)| *ch);
+
+        let ch = existing.or_else(|| {
+            let preferred = name.chars().next().map(|c| c.to_ascii_lowercase());
+            preferred.into_iter()
+                .chain('a'..='z')
+                .chain('A'..='Z')
+                .chain('0'..='9')
+                .find(|c| !legend.contains_key(c))
+        });
+        let Some(ch) = ch else { continue };
+
+        if existing.is_none() {
+            header.push_str(&format!("{ch} {} {name}\n", tile_name(tile)));
+            legend.insert(ch, LegendEntry { tile, marker: Some(name.clone()) });
+        }
+        markers.insert(*p, ch);
+    }
+
+    let mut map = export::to_ascii(build_data);
+    if !markers.is_empty() {
+        let mut rows = map.lines().map(|l| l.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+        for (p, ch) in markers {
+            rows[(board.height as i32 - 1 - p.y) as usize][p.x as usize] = ch;
+        }
+        map = rows.into_iter().map(|r| r.into_iter().collect::<String>() + "\n").collect();
+    }
+
+    format!("{header}{SEPARATOR}\n{map}")
+}
+
+/// Copies a [Prefab] into [BuildData], as the first step of [build].
+struct PrefabBuilder {
+    prefab: Prefab,
+}
+
+impl InitBuilder for PrefabBuilder {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        build_data.board = self.prefab.board.clone();
+        build_data.starting_position = self.prefab.starting_position;
+        build_data.spawns = Some(self.prefab.spawns.clone());
+        build_data.rects = self.prefab.rects.clone();
+        build_data.exit_position = (0..(build_data.board.width * build_data.board.height) as usize)
+            .find(|i| build_data.board.get_tile(*i) == Tile::DownStairs)
+            .map(|i| build_data.board.index_to_xy(i).into());
+        build_data.take_snapshot();
+        Ok(())
+    }
+}
+
+/// Turns a [Prefab] into [BuildData], picking a starting position if it has none.
+/// Unlike the generated maps there is nothing to retry, so an unplayable prefab is
+/// an error.
+pub fn build(prefab: Prefab, depth: u32) -> Result<BuildData, MapGenError> {
+    let (width, height) = (prefab.board.width, prefab.board.height);
+    let needs_start = prefab.starting_position.is_none();
+
+    let mut builder = MapBuilder::new(depth, 0, width, height)
+        .with_starter(Box::new(PrefabBuilder { prefab }));
+    if needs_start {
+        builder = builder.with(area_start_pos::AreaStartingPosition::new());
+    }
+
+    let mut build_data = builder.try_build(0)?;
+    build_data.distances = build_data.starting_position.map(|s| common::distance_map(&build_data.board, s));
+    Ok(build_data)
+}
+
+/// Parses and builds a prefab in the text format, see [parse] and [build].
+pub fn prefab_builder(text: &str, depth: u32) -> Result<BuildData, PrefabError> {
+    build(parse(text, depth)?, depth).map_err(PrefabError::Invalid)
+}
+
+/// Reads and builds the prefab at `path`, see [prefab_builder].
+pub fn load(path: &Path, depth: u32) -> Result<BuildData, PrefabError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| PrefabError::Io(e.to_string()))?;
+    prefab_builder(&text, depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{mapgen::named_builder, pathfind};
+
+    const VAULT: &str = "\
+// A goblin guarding the stairs behind a door.
+g Floor Goblin
+d DoorOpen
+---
+##########
+#@..#....#
+#...d..g>#
+#...#....#
+##########";
+
+    #[test]
+    fn legend() {
+        let prefab = parse(VAULT, 2).unwrap();
+        let board = &prefab.board;
+        assert_eq!((board.width, board.height), (10, 5));
+        // The first map line is the top row.
+        assert_eq!(prefab.starting_position, Some(Point::new(1, 3)));
+        assert_eq!(prefab.spawns, vec![(Point::new(7, 2), "Goblin".to_string())]);
+        assert_eq!(board.get_tile_xy(7, 2), Tile::Floor);
+        assert_eq!(board.get_tile_xy(4, 2), Tile::DoorOpen);
+        assert_eq!(board.get_tile_xy(8, 2), Tile::DownStairs);
+        assert_eq!(board.get_tile_xy(4, 3), Tile::Wall);
+    }
+
+    #[test]
+    fn bad_legend() {
+        let map = "---\n###\n#@#\n###";
+        assert!(matches!(parse(&format!("gFloor Goblin\n{map}"), 0), Err(PrefabError::BadLegend { line: 1 })));
+        assert!(matches!(
+            parse(&format!("// lava\nl Lava\n{map}"), 0),
+            Err(PrefabError::UnknownTile { line: 2, name }) if name == "Lava"
+        ));
+        assert!(matches!(parse("###\n#x#\n###", 0), Err(PrefabError::UnknownChar { line: 2, ch: 'x' })));
+        assert!(matches!(parse("###\n#@#.\n###", 0), Err(PrefabError::RaggedRow { line: 2, expected: 3, found: 4 })));
+        assert!(matches!(parse("#@@#\n####", 0), Err(PrefabError::MultipleStarts { line: 1 })));
+        assert!(matches!(parse("g Floor Goblin\n---\n", 0), Err(PrefabError::Empty)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let build_data = named_builder("simple_rooms", 7, 1).unwrap();
+        let prefab = parse(&to_text(&build_data), 1).unwrap();
+
+        assert_eq!(prefab.board.get_tiles_cloned(), build_data.board.get_tiles_cloned());
+        assert_eq!(prefab.starting_position, build_data.starting_position);
+        let mut spawns = build_data.spawns.clone().unwrap_or_default();
+        spawns.sort();
+        let mut parsed = prefab.spawns;
+        parsed.sort();
+        assert_eq!(parsed, spawns);
+    }
+
+    #[test]
+    fn path_through_vault() {
+        let build_data = prefab_builder(VAULT, 1).unwrap();
+        let board = &build_data.board;
+        let start = build_data.starting_position.unwrap();
+        let exit = build_data.exit_position.unwrap();
+
+        let walkable = board.iter_points().filter(|p| board.is_walkable(*p)).collect::<HashSet<_>>();
+        let path = pathfind::path_astar(start, exit, &walkable, &HashSet::new()).unwrap();
+        assert_eq!(path.back(), Some(&exit));
+        assert!(path.contains(&Point::new(4, 2)), "the only way is through the door");
+        assert_eq!(path.len(), 7);
+
+        // Closing the door cuts the vault off.
+        let mut board = board.clone();
+        board.set_tile_xy(4, 2, Tile::DoorClosed);
+        let walkable = board.iter_points().filter(|p| board.is_walkable(*p)).collect::<HashSet<_>>();
+        assert!(pathfind::path_astar(start, exit, &walkable, &HashSet::new()).is_none());
+    }
+}
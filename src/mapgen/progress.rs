@@ -0,0 +1,52 @@
+use std::sync::{atomic::{AtomicU32, Ordering}, Mutex};
+
+/// Progress of a [MapBuilder](super::MapBuilder) running on another thread. The builder
+/// updates it as it goes and the loading screen reads it every frame.
+#[derive(Debug, Default)]
+pub struct GenProgress {
+    stage: AtomicU32,
+    total_stages: AtomicU32,
+    snapshots: AtomicU32,
+    attempt: AtomicU32,
+    builder: Mutex<&'static str>,
+}
+
+/// A copy of [GenProgress] at one point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressSnapshot {
+    /// Index of the builder currently running, starting at 1.
+    pub stage: u32,
+    pub total_stages: u32,
+    pub snapshots: u32,
+    /// 0 for the first try, see [MapBuilder::build](super::MapBuilder::build).
+    pub attempt: u32,
+    pub builder: &'static str,
+}
+
+impl GenProgress {
+    pub(super) fn start_attempt(&self, attempt: u32, total_stages: u32) {
+        self.attempt.store(attempt, Ordering::Relaxed);
+        self.total_stages.store(total_stages, Ordering::Relaxed);
+        self.stage.store(0, Ordering::Relaxed);
+        self.snapshots.store(0, Ordering::Relaxed);
+    }
+
+    pub(super) fn start_stage(&self, builder: &'static str) {
+        *self.builder.lock().unwrap() = builder;
+        self.stage.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_snapshot(&self) {
+        self.snapshots.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            stage: self.stage.load(Ordering::Relaxed),
+            total_stages: self.total_stages.load(Ordering::Relaxed),
+            snapshots: self.snapshots.load(Ordering::Relaxed),
+            attempt: self.attempt.load(Ordering::Relaxed),
+            builder: *self.builder.lock().unwrap(),
+        }
+    }
+}
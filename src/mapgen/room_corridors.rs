@@ -1,32 +1,42 @@
-use super::{MetaBuilder, common};
+use crate::{random::PRng, rect::Rect};
 
-pub struct RoomCorridors;
+use super::{MetaBuilder, MapGenError, common::{self, CorridorShape}, delaunay};
+
+/// Which pairs of rooms are joined by a corridor.
+#[derive(Clone, Copy, Debug)]
+pub enum RoomLinking {
+    /// Repeatedly connect the closest connected room to the closest disconnected one.
+    Nearest,
+    /// A minimum spanning tree over the room centres, plus up to `extra_edges` randomly
+    /// picked Delaunay edges to create loops.
+    SpanningTree { extra_edges: u32 },
+    /// Every edge of the Delaunay triangulation of the room centres.
+    Delaunay,
+}
+
+pub struct RoomCorridors {
+    linking: RoomLinking,
+    shape: CorridorShape,
+    width: u32,
+}
 
 impl RoomCorridors {
     pub fn new() -> Box<Self> {
-        Box::new(Self {})
+        Self::with_strategy(RoomLinking::Nearest, CorridorShape::Dogleg, 1)
     }
-}
 
-impl MetaBuilder for RoomCorridors {
-    fn build(&mut self, rng: &mut crate::random::PRng, build_data: &mut super::BuildData) {
-        let rooms = {
-            if let Some(ref existing_rooms) = build_data.rects {
-                existing_rooms.clone()
-            } else {
-                panic!(
-                    "{} requires that BuildData rects is not None {build_data:?}",
-                    std::any::type_name::<RoomCorridors>()
-                );
-            }
-        };
+    pub fn with_strategy(linking: RoomLinking, shape: CorridorShape, width: u32) -> Box<Self> {
+        assert!(width > 0);
+        Box::new(Self { linking, shape, width })
+    }
 
+    fn nearest_links(rooms: &[Rect]) -> Vec<(usize, usize)> {
         // Consider the first room as connected (it should always exist).
         let mut connected: Vec<usize> = vec![0];
 
         // All other rooms start out disconnected.
         let mut disconnected: Vec<usize> = Vec::from_iter(1..rooms.len());
-        let mut corridors: Vec<Vec<usize>> = Vec::new();
+        let mut links = Vec::new();
 
         while !disconnected.is_empty() {
 
@@ -43,23 +53,95 @@ impl MetaBuilder for RoomCorridors {
                 .map(|((ci, _), (di, _))| (ci, di))
                 .unwrap();
 
-            // Connected the closest connected and disconnected rooms.
-            let closest_connected_center = rooms[connected[closest_connected]].center();
-            let closest_disconnected_center = rooms[disconnected[closest_disconnected]].center();
-
-            corridors.push(common::draw_corridor(
-                &mut build_data.board,
-                closest_connected_center,
-                closest_disconnected_center,
-                rng.gen::<bool>(),
-            ));
+            links.push((connected[closest_connected], disconnected[closest_disconnected]));
 
             // Transfer newly-connected room index from disconnected to connected.
             connected.push(disconnected.remove(closest_disconnected));
+        }
+        links
+    }
+
+    /// Prim's algorithm over the complete graph of room centres, using manhattan distance.
+    fn spanning_tree_links(rooms: &[Rect]) -> Vec<(usize, usize)> {
+        let mut in_tree = vec![false; rooms.len()];
+        let mut best: Vec<Option<(i32, usize)>> = vec![None; rooms.len()];
+        let mut links = Vec::new();
+
+        let mut current = 0;
+        in_tree[0] = true;
+
+        for _ in 1..rooms.len() {
+            for other in 0..rooms.len() {
+                if in_tree[other] { continue; }
+                let d = rooms[current].center().dist_manhattan(rooms[other].center());
+                if best[other].is_none_or(|(bd, _)| d < bd) {
+                    best[other] = Some((d, current));
+                }
+            }
+
+            let (next, (_, from)) = best.iter()
+                .enumerate()
+                .filter(|(i, _)| !in_tree[*i])
+                .filter_map(|(i, b)| b.map(|b| (i, b)))
+                .min_by_key(|(_, (d, _))| *d)
+                .unwrap();
+
+            links.push((from, next));
+            in_tree[next] = true;
+            current = next;
+        }
+        links
+    }
+
+    fn links(&self, rooms: &[Rect], rng: &mut PRng) -> Vec<(usize, usize)> {
+        if rooms.is_empty() { return Vec::new(); }
+        let centers = rooms.iter().map(|r| r.center()).collect::<Vec<_>>();
+
+        match self.linking {
+            RoomLinking::Nearest => Self::nearest_links(rooms),
+            RoomLinking::SpanningTree { extra_edges } => {
+                let mut links = Self::spanning_tree_links(rooms);
+                let mut candidates = delaunay::triangulate(&centers)
+                    .into_iter()
+                    .filter(|&(a, b)| !links.iter().any(|&(c, d)| (a, b) == (c, d) || (a, b) == (d, c)))
+                    .collect::<Vec<_>>();
+                for _ in 0..extra_edges {
+                    if candidates.is_empty() { break; }
+                    links.push(candidates.swap_remove(rng.gen_range(0..candidates.len())));
+                }
+                links
+            },
+            RoomLinking::Delaunay => {
+                let links = delaunay::triangulate(&centers);
+                // Collinear rooms have no triangulation, make sure they are still connected.
+                if links.len() + 1 < rooms.len() { Self::spanning_tree_links(rooms) } else { links }
+            },
+        }
+    }
+}
+
+impl MetaBuilder for RoomCorridors {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut super::BuildData) -> Result<(), MapGenError> {
+        let Some(rooms) = build_data.rects.clone() else {
+            return Err(MapGenError::MissingData { builder: std::any::type_name::<Self>(), field: "rects" });
+        };
+
+        let mut corridors: Vec<Vec<usize>> = Vec::new();
+
+        for (a, b) in self.links(&rooms, rng) {
+            corridors.push(common::carve_corridor(
+                &mut build_data.board,
+                rooms[a].center(),
+                rooms[b].center(),
+                self.shape,
+                self.width,
+                rng,
+            ));
 
             build_data.take_snapshot();
         }
 
         build_data.corridors = Some(corridors);
+        Ok(())
     }
 }
@@ -0,0 +1,254 @@
+use std::collections::BTreeSet;
+
+use crate::{board::{Board, components::Tile}, point::Point, rect::Rect};
+
+/// A connected stretch of open tiles outside of the rooms, usually one or more
+/// corridors that meet. Two rooms whose floors touch directly share a passage
+/// without any tiles.
+#[derive(Debug, Clone, Default)]
+pub struct Passage {
+    /// Rooms the passage opens into, as indices into `BuildData.rects`.
+    pub rooms: Vec<usize>,
+    /// Corridors (indices into `BuildData.corridors`) that make up the passage.
+    pub corridors: Vec<usize>,
+    /// Tile indices of the passage.
+    pub tiles: Vec<usize>,
+    /// Open or closed doors in the passage.
+    pub doors: Vec<Point>,
+    /// Removing the passage would split the map in two.
+    pub is_chokepoint: bool,
+}
+
+/// How the rooms in `BuildData.rects` are connected. Built by [RoomGraph::new] at the
+/// end of every chain that places rooms, from the finished board so that corridors
+/// joining other corridors are accounted for.
+#[derive(Debug, Clone, Default)]
+pub struct RoomGraph {
+    pub passages: Vec<Passage>,
+    /// Passage indices per room. Only passages joining two or more rooms count, see
+    /// [RoomGraph::stubs].
+    pub adjacency: Vec<Vec<usize>>,
+    /// Passages (indices into `passages`) that open into at most one room, e.g. dead-end
+    /// corridors. They lead nowhere, so they don't make a room less of a leaf, and
+    /// they are left out of the chokepoints and cycles.
+    pub stubs: Vec<usize>,
+    /// Rooms with exactly one way in or out, good spots for treasure.
+    pub leaves: Vec<usize>,
+    /// Rooms that split the map in two when removed, good spots for guards.
+    pub chokepoints: Vec<usize>,
+    /// Number of independent loops, zero if there is only ever one way between two rooms.
+    pub cycles: usize,
+}
+
+impl RoomGraph {
+    pub fn new(board: &Board, rooms: &[Rect], corridors: &[Vec<usize>]) -> Self {
+        let size = (board.width * board.height) as usize;
+        let is_open = |p: Point| board.in_bounds_xy(p.x, p.y)
+            && board.get_tile_xy(p.x as u32, p.y as u32) != Tile::Wall;
+        let room_at = |p: Point| rooms.iter().position(|r| r.contains(p)).filter(|_| is_open(p));
+
+        // Flood fill every open tile outside of the rooms into passages.
+        let mut passage_of: Vec<Option<usize>> = vec![None; size];
+        let mut passages: Vec<Passage> = Vec::new();
+        let mut direct: BTreeSet<(usize, usize)> = BTreeSet::new();
+
+        for p in board.iter_points() {
+            if let Some(room) = room_at(p) {
+                // Rooms touching each other without a passage in between.
+                for n in Point::OCTANT.iter().map(|d| p + *d) {
+                    match room_at(n) {
+                        Some(other) if other != room => { direct.insert((room.min(other), room.max(other))); },
+                        _ => {},
+                    }
+                }
+                continue;
+            }
+
+            let start = board.xy_to_index(p.x as u32, p.y as u32);
+            if !is_open(p) || passage_of[start].is_some() { continue; }
+
+            let id = passages.len();
+            let mut passage = Passage::default();
+            let mut touched = BTreeSet::new();
+            let mut stack = vec![p];
+            passage_of[start] = Some(id);
+
+            while let Some(q) = stack.pop() {
+                let i = board.xy_to_index(q.x as u32, q.y as u32);
+                passage.tiles.push(i);
+                if matches!(board.get_tile(i), Tile::DoorOpen | Tile::DoorClosed) {
+                    passage.doors.push(q);
+                }
+
+                for n in Point::OCTANT.iter().map(|d| q + *d) {
+                    if !is_open(n) { continue; }
+                    if let Some(room) = room_at(n) {
+                        touched.insert(room);
+                        continue;
+                    }
+                    let j = board.xy_to_index(n.x as u32, n.y as u32);
+                    if passage_of[j].is_none() {
+                        passage_of[j] = Some(id);
+                        stack.push(n);
+                    }
+                }
+            }
+
+            passage.tiles.sort();
+            passage.rooms = touched.into_iter().collect();
+            passages.push(passage);
+        }
+
+        for (a, b) in direct {
+            passages.push(Passage { rooms: vec![a, b], ..Default::default() });
+        }
+
+        for (c, corridor) in corridors.iter().enumerate() {
+            let ids = corridor.iter().filter_map(|i| passage_of[*i]).collect::<BTreeSet<_>>();
+            for id in ids {
+                passages[id].corridors.push(c);
+            }
+        }
+
+        let mut adjacency = vec![Vec::new(); rooms.len()];
+        let mut stubs = Vec::new();
+        for (id, passage) in passages.iter().enumerate() {
+            if passage.rooms.len() < 2 {
+                stubs.push(id);
+                continue;
+            }
+            for room in passage.rooms.iter() {
+                adjacency[*room].push(id);
+            }
+        }
+
+        let mut graph = Self {
+            leaves: (0..rooms.len()).filter(|r| adjacency[*r].len() == 1).collect(),
+            passages,
+            adjacency,
+            stubs,
+            ..Default::default()
+        };
+        graph.find_chokepoints();
+        graph
+    }
+
+    /// Rooms reachable from `room` through a single passage.
+    #[allow(dead_code)]
+    pub fn neighbours(&self, room: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency[room].iter()
+            .flat_map(move |p| self.passages[*p].rooms.iter().copied())
+            .filter(move |r| *r != room)
+    }
+
+    /// Finds the articulation points of the graph with a node for every room and every
+    /// passage, and an edge wherever a passage joining rooms opens into a room. Also
+    /// counts its independent cycles. [Stubs](RoomGraph::stubs) have no edges.
+    fn find_chokepoints(&mut self) {
+        let rooms = self.adjacency.len();
+        let n = rooms + self.passages.len();
+        let links = |node: usize| -> Vec<usize> {
+            if node < rooms {
+                self.adjacency[node].iter().map(|p| rooms + p).collect()
+            } else if self.stubs.contains(&(node - rooms)) {
+                Vec::new()
+            } else {
+                self.passages[node - rooms].rooms.clone()
+            }
+        };
+
+        let mut order = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut is_cut = vec![false; n];
+        let mut counter = 0;
+        let mut components = 0;
+        let mut edges = 0;
+
+        for root in 0..n {
+            if order[root] != usize::MAX { continue; }
+            components += 1;
+            order[root] = counter;
+            low[root] = counter;
+            counter += 1;
+
+            // (node, parent, links of the node, next link to look at)
+            let mut stack = vec![(root, usize::MAX, links(root), 0)];
+            let mut root_children = 0;
+
+            while let Some((node, parent, node_links, next)) = stack.last_mut() {
+                let (node, parent) = (*node, *parent);
+                if let Some(&other) = node_links.get(*next) {
+                    *next += 1;
+                    if other == parent { continue; }
+                    if order[other] == usize::MAX {
+                        order[other] = counter;
+                        low[other] = counter;
+                        counter += 1;
+                        if node == root { root_children += 1; }
+                        stack.push((other, node, links(other), 0));
+                    } else {
+                        low[node] = low[node].min(order[other]);
+                    }
+                    continue;
+                }
+
+                edges += node_links.len();
+                stack.pop();
+                if parent == usize::MAX { continue; }
+                low[parent] = low[parent].min(low[node]);
+                if parent != root && low[node] >= order[parent] {
+                    is_cut[parent] = true;
+                }
+            }
+
+            if root_children > 1 {
+                is_cut[root] = true;
+            }
+        }
+
+        // Every edge was counted from both ends.
+        self.cycles = (edges / 2 + components).saturating_sub(n);
+        self.chokepoints = (0..rooms).filter(|r| is_cut[*r]).collect();
+        for (id, passage) in self.passages.iter_mut().enumerate() {
+            passage.is_chokepoint = is_cut[rooms + id];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapgen::prefab;
+
+    /// Three rooms in a row, the left one with a dead-end corridor going south.
+    const ROW_OF_ROOMS: &str = "\
+#############
+#...#...#...#
+#.....@.....#
+#...#...#...#
+##.##########
+##.##########
+#############";
+
+    fn rooms() -> Vec<Rect> {
+        vec![Rect::new(1, 3, 3, 3), Rect::new(5, 3, 3, 3), Rect::new(9, 3, 3, 3)]
+    }
+
+    #[test]
+    fn dead_ends_are_stubs() {
+        let build_data = prefab::prefab_builder(ROW_OF_ROOMS, 1).unwrap();
+        let graph = RoomGraph::new(&build_data.board, &rooms(), &[]);
+
+        assert_eq!(graph.passages.len(), 3);
+        assert_eq!(graph.stubs.len(), 1);
+        let stub = &graph.passages[graph.stubs[0]];
+        assert_eq!(stub.rooms, vec![0]);
+        assert_eq!(stub.tiles.len(), 2);
+
+        assert!(graph.adjacency.iter().all(|passages| !passages.contains(&graph.stubs[0])));
+        assert_eq!(graph.leaves, vec![0, 2]);
+        assert_eq!(graph.chokepoints, vec![1]);
+        assert_eq!(graph.cycles, 0);
+        assert!(!stub.is_chokepoint);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use crate::{random::PRng, rect::Rect, point::Point, board::components::Tile};
+
+use super::{MetaBuilder, BuildData, MapGenError};
+
+const CAVE_PERCENT_FLOOR: u32 = 60;
+const CAVE_NUM_ITERATIONS: u32 = 4;
+
+/// The shape a room is redrawn as by [RoomShapes].
+#[derive(Clone, Copy, Debug)]
+pub enum RoomShape {
+    Rectangle,
+    /// An ellipse inscribed in the room's [Rect].
+    Circle,
+    /// Two bands through the centre of the room.
+    Cross,
+    /// A rectangle with a grid of single-tile pillars.
+    Pillars,
+    /// A rounded, irregular blob smoothed with cellular automata.
+    Cave,
+}
+
+/// Redraws every room in `BuildData.rects` with a randomly chosen [RoomShape]. Every
+/// shape keeps the centre of its [Rect] as floor and is connected to it, so this
+/// builder should run after the starter and before any corridors are drawn.
+pub(super) struct RoomShapes {
+    weights: Vec<(RoomShape, u32)>,
+}
+
+impl RoomShapes {
+    pub fn new() -> Box<Self> {
+        Self::with_weights(vec![
+            (RoomShape::Rectangle, 4),
+            (RoomShape::Circle, 2),
+            (RoomShape::Cross, 1),
+            (RoomShape::Pillars, 1),
+            (RoomShape::Cave, 2),
+        ])
+    }
+
+    pub fn with_weights(weights: Vec<(RoomShape, u32)>) -> Box<Self> {
+        assert!(weights.iter().any(|(_, w)| *w > 0));
+        Box::new(Self { weights })
+    }
+
+    fn choose_shape(&self, rng: &mut PRng) -> RoomShape {
+        rng.choose_weighted(&self.weights, |(_, w)| *w)
+            .map(|(shape, _)| *shape)
+            .expect("with_weights checks for a positive weight")
+    }
+
+    /// Returns a `width * height` mask of the room's floor tiles, in rect-local coordinates.
+    fn shape_mask(shape: RoomShape, rect: &Rect, rng: &mut PRng) -> Vec<bool> {
+        let (w, h) = (rect.width() as i32, rect.height() as i32);
+        let (cx, cy) = ((w - 1) / 2, (h - 1) / 2);
+        let mut mask = vec![true; (w * h) as usize];
+
+        // Shapes other than rectangles need some room to be recognisable.
+        if w < 5 || h < 5 {
+            return mask;
+        }
+
+        match shape {
+            RoomShape::Rectangle => {},
+            RoomShape::Circle => {
+                let (rx, ry) = (w as f32 / 2., h as f32 / 2.);
+                let (fcx, fcy) = ((w - 1) as f32 / 2., (h - 1) as f32 / 2.);
+                for y in 0..h {
+                    for x in 0..w {
+                        let dx = (x as f32 - fcx) / rx;
+                        let dy = (y as f32 - fcy) / ry;
+                        mask[(y * w + x) as usize] = dx * dx + dy * dy <= 1.;
+                    }
+                }
+            },
+            RoomShape::Cross => {
+                let (bx, by) = (i32::max(w / 6, 1), i32::max(h / 6, 1));
+                for y in 0..h {
+                    for x in 0..w {
+                        mask[(y * w + x) as usize] = (x - cx).abs() <= bx || (y - cy).abs() <= by;
+                    }
+                }
+            },
+            RoomShape::Pillars => {
+                // Pillars sit at odd offsets from the centre, leaving the outer ring free.
+                for y in 1..h - 1 {
+                    for x in 1..w - 1 {
+                        if (x - cx) % 2 != 0 && (y - cy) % 2 != 0 {
+                            mask[(y * w + x) as usize] = false;
+                        }
+                    }
+                }
+            },
+            RoomShape::Cave => {
+                for m in mask.iter_mut() {
+                    *m = rng.gen_ratio(CAVE_PERCENT_FLOOR, 100);
+                }
+                for _ in 0..CAVE_NUM_ITERATIONS {
+                    let prev = mask.clone();
+                    for y in 0..h {
+                        for x in 0..w {
+                            // Anything outside of the room counts as wall.
+                            let walls = Point::OCTANT.iter()
+                                .map(|d| (x + d.x, y + d.y))
+                                .filter(|&(nx, ny)| {
+                                    nx < 0 || ny < 0 || nx >= w || ny >= h || !prev[(ny * w + nx) as usize]
+                                })
+                                .count();
+                            mask[(y * w + x) as usize] = walls <= 4;
+                        }
+                    }
+                }
+                // Always keep some space around the centre.
+                for y in cy - 1..=cy + 1 {
+                    for x in cx - 1..=cx + 1 {
+                        mask[(y * w + x) as usize] = true;
+                    }
+                }
+            },
+        }
+
+        mask[(cy * w + cx) as usize] = true;
+        Self::keep_connected(&mut mask, w, h, (cx, cy));
+        mask
+    }
+
+    /// Removes every floor tile in `mask` that can't be reached from `start`.
+    fn keep_connected(mask: &mut [bool], w: i32, h: i32, start: (i32, i32)) {
+        let mut reached = vec![false; mask.len()];
+        let mut queue = VecDeque::from([start]);
+        reached[(start.1 * w + start.0) as usize] = true;
+
+        while let Some((x, y)) = queue.pop_front() {
+            for d in Point::CARDINALS {
+                let (nx, ny) = (x + d.x, y + d.y);
+                if nx < 0 || ny < 0 || nx >= w || ny >= h { continue; }
+                let i = (ny * w + nx) as usize;
+                if mask[i] && !reached[i] {
+                    reached[i] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        for (m, r) in mask.iter_mut().zip(reached) {
+            *m = *m && r;
+        }
+    }
+}
+
+impl MetaBuilder for RoomShapes {
+    fn build(&mut self, rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let Some(rooms) = build_data.rects.clone() else {
+            return Err(MapGenError::MissingData { builder: std::any::type_name::<Self>(), field: "rects" });
+        };
+
+        for room in rooms.iter() {
+            let shape = self.choose_shape(rng);
+            let mask = Self::shape_mask(shape, room, rng);
+
+            for ((x, y), floor) in room.iter_xy().zip(mask) {
+                let tile = if floor { Tile::Floor } else { Tile::Wall };
+                build_data.board.set_tile_xy(x, y, tile);
+            }
+            build_data.take_snapshot();
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use super::{InitBuilder, common};
+use super::{InitBuilder, MapGenError, common};
 
 use crate::{config, rect::Rect, board::components::Tile};
 
@@ -17,11 +17,11 @@ impl SimpleRoomBuilder {
 }
 
 impl InitBuilder for SimpleRoomBuilder {
-    fn build(&mut self, rng: &mut crate::random::PRng, build_data: &mut super::BuildData) {
+    fn build(&mut self, rng: &mut crate::random::PRng, build_data: &mut super::BuildData) -> Result<(), MapGenError> {
         let mut rooms: Vec<Rect> = Vec::new();
 
         for _ in 0..NUM_TRIES {
-            let new_room = common::random_rect(
+            let Some(new_room) = common::random_rect(
                 MIN_WIDTH,
                 MAX_WIDTH,
                 MIN_HEIGHT,
@@ -31,7 +31,13 @@ impl InitBuilder for SimpleRoomBuilder {
                 1,
                 build_data.board.height - 1,
                 rng,
-            );
+            ) else {
+                return Err(MapGenError::BoardTooSmall {
+                    builder: std::any::type_name::<Self>(),
+                    width: build_data.board.width,
+                    height: build_data.board.height,
+                });
+            };
 
             if !rooms.iter().any(|r| new_room.intersects(r, 1)) {
                 build_data.board.set_rect(&new_room, Tile::Floor);
@@ -40,6 +46,10 @@ impl InitBuilder for SimpleRoomBuilder {
             }
         }
 
+        if rooms.is_empty() {
+            return Err(MapGenError::NoRooms { builder: std::any::type_name::<Self>() });
+        }
         build_data.rects = Some(rooms);
+        Ok(())
     }
 }
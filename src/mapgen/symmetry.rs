@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use crate::{random::PRng, rect::Rect, point::Point, board::{Board, components::Tile}};
+
+use super::{MetaBuilder, BuildData, MapGenError, common};
+
+const MIN_BOARD_SIZE: u32 = 4;
+
+/// How [Symmetry] makes the board symmetric.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub enum SymmetryMode {
+    /// The left half is mirrored onto the right half.
+    Horizontal,
+    /// The bottom half is mirrored onto the top half.
+    Vertical,
+    /// Horizontal and then vertical, the bottom left quarter ends up in every corner.
+    Both,
+    /// The bottom half is rotated by 180° around the centre onto the top half.
+    Rotate180,
+}
+
+/// A single reflection or rotation of the board.
+#[derive(Clone, Copy, Debug)]
+enum Transform {
+    MirrorX,
+    MirrorY,
+    Rotate,
+}
+
+impl Transform {
+    fn apply(self, board: &Board, p: Point) -> Point {
+        let (w, h) = (board.width as i32, board.height as i32);
+        match self {
+            Transform::MirrorX => Point::new(w - 1 - p.x, p.y),
+            Transform::MirrorY => Point::new(p.x, h - 1 - p.y),
+            Transform::Rotate => Point::new(w - 1 - p.x, h - 1 - p.y),
+        }
+    }
+
+    fn apply_index(self, board: &Board, i: usize) -> usize {
+        let p = self.apply(board, board.index_to_xy(i).into());
+        board.xy_to_index(p.x as u32, p.y as u32)
+    }
+
+    /// Whether `p` is kept as it is. Every other tile is overwritten with its image.
+    fn is_source(self, board: &Board, p: Point) -> bool {
+        let (mid_x, mid_y) = ((board.width as i32 - 1) / 2, (board.height as i32 - 1) / 2);
+        match self {
+            Transform::MirrorX => p.x <= mid_x,
+            Transform::MirrorY => p.y <= mid_y,
+            Transform::Rotate => {
+                let i = board.xy_to_index(p.x as u32, p.y as u32);
+                i <= (board.width * board.height - 1) as usize / 2
+            },
+        }
+    }
+
+    fn apply_rect(self, board: &Board, r: &Rect) -> Rect {
+        let a = self.apply(board, Point::new(r.x1 as i32, r.y1 as i32));
+        let b = self.apply(board, Point::new(r.x2 as i32, r.y2 as i32));
+        Rect {
+            x1: a.x.min(b.x) as u32,
+            y1: a.y.min(b.y) as u32,
+            x2: a.x.max(b.x) as u32,
+            y2: a.y.max(b.y) as u32,
+        }
+    }
+
+    /// The part of `r` in the source half, if any.
+    fn clip_rect(self, board: &Board, r: &Rect) -> Option<Rect> {
+        let (mid_x, mid_y) = ((board.width - 1) / 2, (board.height - 1) / 2);
+        let mut clipped = *r;
+        match self {
+            Transform::MirrorX => clipped.x2 = clipped.x2.min(mid_x),
+            Transform::MirrorY | Transform::Rotate => clipped.y2 = clipped.y2.min(mid_y),
+        }
+        (clipped.x1 <= clipped.x2 && clipped.y1 <= clipped.y2).then_some(clipped)
+    }
+}
+
+/// Two rects that share a side (or overlap) along one axis form a single rect.
+fn merge_rects(a: &Rect, b: &Rect) -> Option<Rect> {
+    let same_x = a.x1 == b.x1 && a.x2 == b.x2;
+    let same_y = a.y1 == b.y1 && a.y2 == b.y2;
+    let touch_x = a.x1 <= b.x2 + 1 && b.x1 <= a.x2 + 1;
+    let touch_y = a.y1 <= b.y2 + 1 && b.y1 <= a.y2 + 1;
+    ((same_x && touch_y) || (same_y && touch_x)).then(|| Rect {
+        x1: a.x1.min(b.x1),
+        y1: a.y1.min(b.y1),
+        x2: a.x2.max(b.x2),
+        y2: a.y2.max(b.y2),
+    })
+}
+
+/// Makes the board symmetric, see [SymmetryMode]. Rooms and corridors in the half that
+/// gets overwritten are dropped and the others are copied along, so `rects` and
+/// `corridors` keep matching the board. If the copy leaves parts of the map cut off
+/// from each other, symmetric tunnels are dug to join them.
+///
+/// Run this before the builders that place doors, the starting position or the exit.
+pub(super) struct Symmetry {
+    mode: SymmetryMode,
+}
+
+impl Symmetry {
+    pub fn new(mode: SymmetryMode) -> Box<Self> {
+        Box::new(Self { mode })
+    }
+
+    fn apply(transform: Transform, build_data: &mut BuildData) {
+        let board = &mut build_data.board;
+
+        for p in board.iter_points().collect::<Vec<_>>() {
+            if transform.is_source(board, p) { continue; }
+            let source = transform.apply(board, p);
+            let tile = board.get_tile_xy(source.x as u32, source.y as u32);
+            board.set_tile_xy(p.x as u32, p.y as u32, tile);
+        }
+
+        if let Some(rooms) = build_data.rects.take() {
+            let mut mirrored = Vec::new();
+            for room in rooms.iter().filter_map(|r| transform.clip_rect(board, r)) {
+                let image = transform.apply_rect(board, &room);
+                match merge_rects(&room, &image) {
+                    Some(merged) => mirrored.push(merged),
+                    None => mirrored.extend([room, image]),
+                }
+            }
+            build_data.rects = Some(mirrored);
+        }
+
+        if let Some(corridors) = build_data.corridors.take() {
+            let mirrored = corridors.into_iter()
+                .map(|corridor| {
+                    let kept = corridor.into_iter()
+                        .filter(|i| transform.is_source(board, board.index_to_xy(*i).into()))
+                        .collect::<Vec<_>>();
+                    let images = kept.iter()
+                        .map(|i| transform.apply_index(board, *i))
+                        .filter(|i| !kept.contains(i))
+                        .collect::<Vec<_>>();
+                    [kept, images].concat()
+                })
+                .filter(|c| !c.is_empty())
+                .collect();
+            build_data.corridors = Some(mirrored);
+        }
+
+        if let Some(start) = build_data.starting_position {
+            if !transform.is_source(board, start) {
+                build_data.starting_position = Some(transform.apply(board, start));
+            }
+        }
+        build_data.distances = None;
+
+        Self::connect(transform, build_data);
+        build_data.take_snapshot();
+    }
+
+    /// Digs symmetric tunnels until every open tile can be reached from the start
+    /// (or the first open tile if there is no start yet).
+    fn connect(transform: Transform, build_data: &mut BuildData) {
+        let board = &mut build_data.board;
+        let size = (board.width * board.height) as usize;
+        let is_open = |board: &Board, i: usize| board.get_tile(i) != Tile::Wall;
+
+        let Some(anchor) = build_data.starting_position
+            .or_else(|| (0..size).find(|i| is_open(board, *i)).map(|i| board.index_to_xy(i).into()))
+        else {
+            return;
+        };
+
+        // Every tunnel joins at least one more region, so this always ends.
+        loop {
+            let reached = common::distance_map(board, anchor);
+            if (0..size).all(|i| !is_open(board, i) || reached[i].is_some()) {
+                return;
+            }
+
+            let Some(tunnel) = Self::find_tunnel(board, &reached) else { return };
+            let mut corridor = Vec::new();
+            for i in tunnel {
+                for j in [i, transform.apply_index(board, i)] {
+                    if !is_open(board, j) {
+                        board.set_tile(j, Tile::Floor);
+                        corridor.push(j);
+                    }
+                }
+            }
+            if let Some(corridors) = build_data.corridors.as_mut() {
+                corridors.push(corridor);
+            }
+        }
+    }
+
+    /// The shortest line of tiles (moving in the four cardinal directions and keeping
+    /// off the outer edge) from the reached area to an open tile that was not reached.
+    fn find_tunnel(board: &Board, reached: &[Option<u32>]) -> Option<Vec<usize>> {
+        let (w, h) = (board.width as i32, board.height as i32);
+        let mut parent: Vec<Option<usize>> = vec![None; reached.len()];
+        let mut queue = VecDeque::new();
+        for (i, r) in reached.iter().enumerate() {
+            if r.is_some() {
+                parent[i] = Some(i);
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            let p: Point = board.index_to_xy(i).into();
+            for d in Point::CARDINALS {
+                let n = p + d;
+                if n.x < 1 || n.y < 1 || n.x >= w - 1 || n.y >= h - 1 { continue; }
+                let j = board.xy_to_index(n.x as u32, n.y as u32);
+                if parent[j].is_some() { continue; }
+                parent[j] = Some(i);
+
+                if board.get_tile(j) != Tile::Wall {
+                    let mut tunnel = Vec::new();
+                    let mut k = i;
+                    while reached[k].is_none() {
+                        tunnel.push(k);
+                        k = parent[k].unwrap();
+                    }
+                    return Some(tunnel);
+                }
+                queue.push_back(j);
+            }
+        }
+        None
+    }
+}
+
+impl MetaBuilder for Symmetry {
+    fn build(&mut self, _rng: &mut PRng, build_data: &mut BuildData) -> Result<(), MapGenError> {
+        let (width, height) = (build_data.board.width, build_data.board.height);
+        if width < MIN_BOARD_SIZE || height < MIN_BOARD_SIZE {
+            return Err(MapGenError::BoardTooSmall { builder: std::any::type_name::<Self>(), width, height });
+        }
+
+        let transforms: &[Transform] = match self.mode {
+            SymmetryMode::Horizontal => &[Transform::MirrorX],
+            SymmetryMode::Vertical => &[Transform::MirrorY],
+            SymmetryMode::Both => &[Transform::MirrorX, Transform::MirrorY],
+            SymmetryMode::Rotate180 => &[Transform::Rotate],
+        };
+        for transform in transforms {
+            Self::apply(*transform, build_data);
+        }
+        Ok(())
+    }
+}
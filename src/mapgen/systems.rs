@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use bevy::{prelude::*, tasks::{AsyncComputeTaskPool, Task}};
+use futures_lite::future;
+
+use crate::{GameSeed, PrefabPath, random::Rngs, state::MainState, theme::{ActiveTheme, Themes}};
+
+use super::{BuildData, GenProgress, load_map, themed_builder};
+
+/// The map currently being built on the [AsyncComputeTaskPool].
+#[derive(Resource)]
+pub struct MapGenTask(Task<BuildData>);
+
+/// Progress of the [MapGenTask], shared with the task itself.
+#[derive(Resource, Clone)]
+pub struct MapGenProgress(pub Arc<GenProgress>);
+
+pub fn start_generation(
+    mut commands: Commands,
+    game_seed: Res<GameSeed>,
+    themes: Res<Themes>,
+    prefab_path: Res<PrefabPath>,
+) {
+    let progress = Arc::new(GenProgress::default());
+    let task_progress = progress.clone();
+    let (seed, depth) = (game_seed.0, 0);
+    let theme = themes.pick(seed, depth).clone();
+    let task_theme = theme.clone();
+    let prefab_path = prefab_path.0.clone();
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        if let Some(path) = prefab_path {
+            match load_map(&path, depth) {
+                Ok(build_data) => return build_data,
+                Err(e) => error!("could not load map, generating one instead: {e}"),
+            }
+        }
+        themed_builder(&task_theme, seed, depth, Some(task_progress))
+    });
+
+    commands.insert_resource(MapGenTask(task));
+    commands.insert_resource(MapGenProgress(progress));
+    debug!("map generation started, theme {}", theme.name);
+    commands.insert_resource(ActiveTheme(theme));
+    commands.insert_resource(Rngs::new(seed, depth));
+}
+
+/// Moves on to [MainState::Game] once the [MapGenTask] is done, with its result
+/// as the [BuildData] resource.
+pub fn poll_generation(
+    mut commands: Commands,
+    mut task: ResMut<MapGenTask>,
+    mut next_state: ResMut<NextState<MainState>>,
+) {
+    let Some(build_data) = future::block_on(future::poll_once(&mut task.0)) else { return };
+
+    commands.insert_resource(build_data);
+    commands.remove_resource::<MapGenTask>();
+    commands.remove_resource::<MapGenProgress>();
+    next_state.set(MainState::Game);
+    debug!("map generation finished");
+}
@@ -0,0 +1,920 @@
+//! Reading and writing maps made with the [Tiled](https://www.mapeditor.org) editor,
+//! both as JSON (`.tmj`/`.json`) and as TMX (`.tmx`).
+//!
+//! When importing, every non-empty tile of the visible tile layers becomes a [Tile].
+//! Which one is looked up in the tileset: a tile with a string property `tile`, or
+//! else the class (or type) of the tile, naming a [Tile] variant like `Floor` or
+//! `DoorClosed`. Later layers are drawn over earlier ones and empty tiles are walls.
+//!
+//! Objects in the visible object layers are read by their class (or type):
+//!
+//! * `start`: the starting position,
+//! * `spawn`: a monster, named by the property `monster` or else the object's name,
+//! * `room`: a rectangle that becomes one of the [BuildData::rects].
+//!
+//! Other objects are left alone, so designers can keep notes in the map. Only finite,
+//! orthogonal maps with embedded tilesets and CSV (or plain JSON array) layer data
+//! are supported.
+//!
+//! Exported maps use a tileset with one tile per [Tile], drawn in [TILESET_IMAGE]
+//! which [tileset_image] renders, so they can be loaded again as they are.
+
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use xml::{escape::escape_str_attribute, reader::{EventReader, XmlEvent}};
+
+use crate::{board::{Board, components::Tile}, point::Point, rect::Rect};
+
+use super::{BuildData, MapGenError, export, prefab::{self, Prefab}};
+
+/// File name of the tileset image referenced by exported maps.
+pub const TILESET_IMAGE: &str = "bevogst_tiles.png";
+const TILESET_NAME: &str = "bevogst";
+const TILE_SIZE: u32 = 16;
+
+/// The top bits of a gid say whether the tile is flipped or rotated.
+const GID_MASK: u32 = 0x0fff_ffff;
+
+const START_CLASS: &str = "start";
+const SPAWN_CLASS: &str = "spawn";
+const ROOM_CLASS: &str = "room";
+const TILE_PROPERTY: &str = "tile";
+const MONSTER_PROPERTY: &str = "monster";
+
+/// Reasons a Tiled map can't be loaded or saved.
+#[derive(Debug)]
+pub enum TiledError {
+    Io(String),
+    /// The file is not valid JSON or XML, or doesn't look like a Tiled map.
+    Parse(String),
+    /// The map uses a Tiled feature we can't read, see the [module documentation](self).
+    Unsupported(String),
+    /// A tile in the tileset names a tile that doesn't exist.
+    UnknownTile { name: String },
+    /// A layer uses a tile that doesn't stand for any [Tile].
+    UnmappedGid { layer: String, gid: u32 },
+    /// A tile layer doesn't have one tile per map tile.
+    BadLayerSize { layer: String, expected: usize, found: usize },
+    /// An object lies outside the map, or a room has no area.
+    BadObject { id: u32, reason: &'static str },
+    TooSmall { width: u32, height: u32 },
+    MultipleStarts { id: u32 },
+    /// The map was read but is not playable.
+    Invalid(MapGenError),
+}
+
+impl Display for TiledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TiledError::Io(e) => write!(f, "{e}"),
+            TiledError::Parse(e) => write!(f, "not a Tiled map: {e}"),
+            TiledError::Unsupported(what) => write!(f, "unsupported: {what}"),
+            TiledError::UnknownTile { name } => write!(f, "unknown tile {name} in tileset"),
+            TiledError::UnmappedGid { layer, gid } => {
+                write!(f, "layer {layer}: tile {gid} has no `{TILE_PROPERTY}` property or tile class")
+            },
+            TiledError::BadLayerSize { layer, expected, found } => {
+                write!(f, "layer {layer}: expected {expected} tiles, found {found}")
+            },
+            TiledError::BadObject { id, reason } => write!(f, "object {id}: {reason}"),
+            TiledError::TooSmall { width, height } => write!(f, "map of {width}x{height} is too small"),
+            TiledError::MultipleStarts { id } => write!(f, "object {id}: second starting position"),
+            TiledError::Invalid(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+/// The parts of the Tiled map format we use. The field names follow the JSON format,
+/// the TMX reader fills in the same structure.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TiledMap {
+    #[serde(rename = "type")]
+    kind: String,
+    version: String,
+    orientation: String,
+    renderorder: String,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    infinite: bool,
+    nextlayerid: u32,
+    nextobjectid: u32,
+    layers: Vec<Layer>,
+    tilesets: Vec<Tileset>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Layer {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default = "default_opacity")]
+    opacity: f64,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+    #[serde(flatten)]
+    kind: LayerKind,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn default_opacity() -> f64 {
+    1.
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum LayerKind {
+    TileLayer {
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        #[serde(default)]
+        data: LayerData,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<String>,
+    },
+    ObjectGroup {
+        #[serde(default)]
+        objects: Vec<Object>,
+    },
+    Group {
+        #[serde(default)]
+        layers: Vec<Layer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum LayerData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+impl Default for LayerData {
+    fn default() -> Self {
+        LayerData::Gids(Vec::new())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Object {
+    id: u32,
+    name: String,
+    /// Called class in Tiled 1.9, type before and after.
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing)]
+    class: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    point: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Tileset {
+    firstgid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    tilecount: u32,
+    columns: u32,
+    image: String,
+    imagewidth: u32,
+    imageheight: u32,
+    margin: u32,
+    spacing: u32,
+    tiles: Vec<TilesetTile>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct TilesetTile {
+    id: u32,
+    #[serde(rename = "type", skip_serializing_if = "String::is_empty")]
+    kind: String,
+    #[serde(skip_serializing)]
+    class: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Property {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    value: serde_json::Value,
+}
+
+impl Property {
+    fn string(name: &str, value: &str) -> Self {
+        Self { name: name.to_string(), kind: "string".to_string(), value: value.into() }
+    }
+
+    fn value_string(&self) -> String {
+        match &self.value {
+            serde_json::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+fn find_property(properties: &[Property], name: &str) -> Option<String> {
+    properties.iter().find(|p| p.name == name).map(Property::value_string)
+}
+
+/// The class of an object or tile, whichever of `class` and `type` is set.
+fn class_of<'a>(class: &'a str, kind: &'a str) -> &'a str {
+    if class.is_empty() { kind } else { class }
+}
+
+/// Calls `f` for every visible layer, looking inside group layers.
+fn visit_layers<'a>(layers: &'a [Layer], f: &mut impl FnMut(&'a Layer) -> Result<(), TiledError>) -> Result<(), TiledError> {
+    for layer in layers.iter().filter(|l| l.visible) {
+        match &layer.kind {
+            LayerKind::Group { layers } => visit_layers(layers, f)?,
+            _ => f(layer)?,
+        }
+    }
+    Ok(())
+}
+
+impl TiledMap {
+    fn gid_tiles(&self) -> Result<BTreeMap<u32, Tile>, TiledError> {
+        let mut tiles = BTreeMap::new();
+        for tileset in self.tilesets.iter() {
+            if let Some(source) = &tileset.source {
+                return Err(TiledError::Unsupported(format!("external tileset {source}, embed it in the map")));
+            }
+            for tile in tileset.tiles.iter() {
+                let name = find_property(&tile.properties, TILE_PROPERTY)
+                    .unwrap_or_else(|| class_of(&tile.class, &tile.kind).to_string());
+                if name.is_empty() { continue; }
+                let t = prefab::tile_from_name(&name).ok_or(TiledError::UnknownTile { name })?;
+                tiles.insert(tileset.firstgid + tile.id, t);
+            }
+        }
+        Ok(tiles)
+    }
+
+    /// The tile under a point in pixels, `None` if it's outside the map.
+    fn tile_at(&self, x: f64, y: f64) -> Option<Point> {
+        let (col, row) = ((x / self.tilewidth as f64).floor(), (y / self.tileheight as f64).floor());
+        if col < 0. || row < 0. || col >= self.width as f64 || row >= self.height as f64 {
+            return None;
+        }
+        Some(Point::new(col as i32, (self.height - 1) as i32 - row as i32))
+    }
+
+    /// The tiles covered by a rectangle in pixels, `y` being its top edge.
+    fn tile_rect(&self, x: f64, y: f64, width: f64, height: f64) -> Option<Rect> {
+        // Nudge the far edges inwards so a room ending on a tile border doesn't
+        // spill into the next tile.
+        let top_left = self.tile_at(x, y)?;
+        let bottom_right = self.tile_at(x + width - 0.5, y + height - 0.5)?;
+        Some(Rect {
+            x1: top_left.x as u32,
+            y1: bottom_right.y as u32,
+            x2: bottom_right.x as u32,
+            y2: top_left.y as u32,
+        })
+    }
+
+    fn to_prefab(&self, depth: u32) -> Result<Prefab, TiledError> {
+        if self.infinite {
+            return Err(TiledError::Unsupported("infinite maps".to_string()));
+        }
+        if !self.orientation.is_empty() && self.orientation != "orthogonal" {
+            return Err(TiledError::Unsupported(format!("{} maps", self.orientation)));
+        }
+        if self.width < 2 || self.height < 2 {
+            return Err(TiledError::TooSmall { width: self.width, height: self.height });
+        }
+        if self.tilewidth == 0 || self.tileheight == 0 {
+            return Err(TiledError::Parse("map has no tile size".to_string()));
+        }
+
+        let gid_tiles = self.gid_tiles()?;
+        let mut prefab = Prefab {
+            board: Board::new(depth, self.width, self.height),
+            starting_position: None,
+            spawns: Vec::new(),
+            rects: None,
+        };
+        let mut rects = Vec::new();
+        let size = (self.width * self.height) as usize;
+
+        visit_layers(&self.layers, &mut |layer| {
+            match &layer.kind {
+                LayerKind::TileLayer { data, encoding, compression, .. } => {
+                    let gids = match data {
+                        LayerData::Gids(gids) if compression.is_none() => gids,
+                        _ => {
+                            let encoding = encoding.as_deref().unwrap_or("csv");
+                            let compression = compression.as_deref().map(|c| format!(", {c}")).unwrap_or_default();
+                            return Err(TiledError::Unsupported(format!(
+                                "layer {}: {encoding}{compression} tile layer format, use CSV", layer.name,
+                            )));
+                        },
+                    };
+                    if gids.len() != size {
+                        return Err(TiledError::BadLayerSize { layer: layer.name.clone(), expected: size, found: gids.len() });
+                    }
+
+                    for (i, gid) in gids.iter().map(|g| g & GID_MASK).enumerate() {
+                        if gid == 0 { continue; }
+                        let tile = *gid_tiles.get(&gid)
+                            .ok_or(TiledError::UnmappedGid { layer: layer.name.clone(), gid })?;
+                        let (col, row) = (i as u32 % self.width, i as u32 / self.width);
+                        prefab.board.set_tile_xy(col, self.height - 1 - row, tile);
+                    }
+                },
+                LayerKind::ObjectGroup { objects } => {
+                    for object in objects {
+                        // Tile objects are anchored at their bottom left corner.
+                        let y = if object.gid.is_some() { object.y - object.height } else { object.y };
+                        let id = object.id;
+                        let outside = TiledError::BadObject { id, reason: "outside the map" };
+
+                        match class_of(&object.class, &object.kind) {
+                            START_CLASS => {
+                                if prefab.starting_position.is_some() {
+                                    return Err(TiledError::MultipleStarts { id });
+                                }
+                                prefab.starting_position = Some(self.tile_at(object.x, y).ok_or(outside)?);
+                            },
+                            SPAWN_CLASS => {
+                                let monster = find_property(&object.properties, MONSTER_PROPERTY)
+                                    .unwrap_or_else(|| object.name.clone());
+                                if monster.is_empty() {
+                                    return Err(TiledError::BadObject { id, reason: "spawn without a monster" });
+                                }
+                                prefab.spawns.push((self.tile_at(object.x, y).ok_or(outside)?, monster));
+                            },
+                            ROOM_CLASS => {
+                                if object.width <= 0. || object.height <= 0. {
+                                    return Err(TiledError::BadObject { id, reason: "room without an area" });
+                                }
+                                rects.push(self.tile_rect(object.x, y, object.width, object.height).ok_or(outside)?);
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+                LayerKind::Group { .. } | LayerKind::Other => {},
+            }
+            Ok(())
+        })?;
+
+        if !rects.is_empty() {
+            prefab.rects = Some(rects);
+        }
+        Ok(prefab)
+    }
+
+    fn from_build_data(build_data: &BuildData) -> Self {
+        let board = &build_data.board;
+        let (tw, th) = (TILE_SIZE as f64, TILE_SIZE as f64);
+        let row_of = |y: i32| (board.height as i32 - 1 - y) as f64;
+
+        let gids = (0..board.height).rev()
+            .flat_map(|y| (0..board.width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let tile = board.get_tile_xy(x, y);
+                1 + prefab::TILE_NAMES.iter().position(|(t, _)| *t == tile).unwrap() as u32
+            })
+            .collect();
+
+        let mut next_id = 1;
+        let mut object = |kind: &str, name: &str| {
+            next_id += 1;
+            Object { id: next_id - 1, name: name.to_string(), kind: kind.to_string(), ..Default::default() }
+        };
+        let marker = |object: Object, p: Point| Object {
+            x: (p.x as f64 + 0.5) * tw,
+            y: (row_of(p.y) + 0.5) * th,
+            point: true,
+            ..object
+        };
+
+        let rooms = build_data.rects.iter().flatten()
+            .enumerate()
+            .map(|(i, r)| Object {
+                x: r.x1 as f64 * tw,
+                y: row_of(r.y2 as i32) * th,
+                width: r.width() as f64 * tw,
+                height: r.height() as f64 * th,
+                ..object(ROOM_CLASS, &format!("room {i}"))
+            })
+            .collect::<Vec<_>>();
+
+        let mut markers = Vec::new();
+        if let Some(start) = build_data.starting_position {
+            markers.push(marker(object(START_CLASS, "start"), start));
+        }
+        for (p, name) in build_data.spawns.iter().flatten() {
+            markers.push(marker(object(SPAWN_CLASS, name), *p));
+        }
+
+        let tiles = prefab::TILE_NAMES.iter()
+            .enumerate()
+            .map(|(i, (_, name))| TilesetTile {
+                id: i as u32,
+                properties: vec![Property::string(TILE_PROPERTY, name)],
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let layer = |id: u32, name: &str, kind: LayerKind| Layer {
+            id,
+            name: name.to_string(),
+            visible: true,
+            opacity: 1.,
+            x: 0,
+            y: 0,
+            kind,
+        };
+
+        Self {
+            kind: "map".to_string(),
+            version: "1.10".to_string(),
+            orientation: "orthogonal".to_string(),
+            renderorder: "right-down".to_string(),
+            width: board.width,
+            height: board.height,
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            infinite: false,
+            nextlayerid: 4,
+            nextobjectid: next_id,
+            layers: vec![
+                layer(1, "tiles", LayerKind::TileLayer {
+                    width: board.width,
+                    height: board.height,
+                    data: LayerData::Gids(gids),
+                    encoding: None,
+                    compression: None,
+                }),
+                layer(2, "rooms", LayerKind::ObjectGroup { objects: rooms }),
+                layer(3, "markers", LayerKind::ObjectGroup { objects: markers }),
+            ],
+            tilesets: vec![Tileset {
+                firstgid: 1,
+                source: None,
+                name: TILESET_NAME.to_string(),
+                tilewidth: TILE_SIZE,
+                tileheight: TILE_SIZE,
+                tilecount: tiles.len() as u32,
+                columns: tiles.len() as u32,
+                image: TILESET_IMAGE.to_string(),
+                imagewidth: TILE_SIZE * tiles.len() as u32,
+                imageheight: TILE_SIZE,
+                margin: 0,
+                spacing: 0,
+                tiles,
+            }],
+        }
+    }
+}
+
+/// An XML element with its attributes, children and text, as read from a TMX file.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: BTreeMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn parse(text: &str) -> Result<Element, TiledError> {
+        let mut stack = vec![Element::default()];
+        for event in EventReader::new(text.as_bytes()) {
+            match event.map_err(|e| TiledError::Parse(e.to_string()))? {
+                XmlEvent::StartElement { name, attributes, .. } => stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes.into_iter().map(|a| (a.name.local_name, a.value)).collect(),
+                    ..Default::default()
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(element);
+                },
+                XmlEvent::Characters(s) | XmlEvent::CData(s) => stack.last_mut().unwrap().text.push_str(&s),
+                _ => {},
+            }
+        }
+        stack.pop()
+            .and_then(|root| root.children.into_iter().next())
+            .ok_or(TiledError::Parse("empty document".to_string()))
+    }
+
+    fn attr(&self, name: &str) -> &str {
+        self.attributes.get(name).map(String::as_str).unwrap_or("")
+    }
+
+    fn num<T: std::str::FromStr + Default>(&self, name: &str) -> Result<T, TiledError> {
+        match self.attributes.get(name) {
+            Some(v) => v.parse().map_err(|_| TiledError::Parse(format!("<{}> {name}=\"{v}\" is not a number", self.name))),
+            None => Ok(T::default()),
+        }
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn properties(&self) -> Vec<Property> {
+        self.children("properties")
+            .flat_map(|p| p.children("property"))
+            .map(|p| Property {
+                name: p.attr("name").to_string(),
+                kind: p.attr("type").to_string(),
+                value: p.attributes.get("value").cloned().unwrap_or_else(|| p.text.clone()).into(),
+            })
+            .collect()
+    }
+
+    fn layers(&self) -> Result<Vec<Layer>, TiledError> {
+        let mut layers = Vec::new();
+        for child in self.children.iter() {
+            let kind = match child.name.as_str() {
+                "layer" => {
+                    let data = child.children("data").next();
+                    let encoding = data.map(|d| d.attr("encoding")).filter(|e| !e.is_empty());
+                    let compression = data.map(|d| d.attr("compression")).filter(|c| !c.is_empty());
+                    let gids = match (data, encoding) {
+                        (None, _) => LayerData::default(),
+                        (Some(data), Some("csv")) => LayerData::Gids(data.text
+                            .split(',')
+                            .map(|g| g.trim().parse().map_err(|_| TiledError::Parse(format!("bad tile {}", g.trim()))))
+                            .collect::<Result<_, _>>()?),
+                        (Some(data), None) => LayerData::Gids(data.children("tile")
+                            .map(|t| t.num("gid"))
+                            .collect::<Result<_, _>>()?),
+                        (Some(data), Some(_)) => LayerData::Encoded(data.text.trim().to_string()),
+                    };
+                    LayerKind::TileLayer {
+                        width: child.num("width")?,
+                        height: child.num("height")?,
+                        data: gids,
+                        encoding: encoding.map(str::to_string),
+                        compression: compression.map(str::to_string),
+                    }
+                },
+                "objectgroup" => LayerKind::ObjectGroup {
+                    objects: child.children("object")
+                        .map(|o| Ok(Object {
+                            id: o.num("id")?,
+                            name: o.attr("name").to_string(),
+                            kind: o.attr("type").to_string(),
+                            class: o.attr("class").to_string(),
+                            x: o.num("x")?,
+                            y: o.num("y")?,
+                            width: o.num("width")?,
+                            height: o.num("height")?,
+                            gid: o.attributes.contains_key("gid").then(|| o.num("gid")).transpose()?,
+                            point: o.children("point").next().is_some(),
+                            properties: o.properties(),
+                        }))
+                        .collect::<Result<_, TiledError>>()?,
+                },
+                "group" => LayerKind::Group { layers: child.layers()? },
+                "imagelayer" => LayerKind::Other,
+                _ => continue,
+            };
+            layers.push(Layer {
+                id: child.num("id")?,
+                name: child.attr("name").to_string(),
+                visible: child.attr("visible") != "0",
+                opacity: child.attributes.get("opacity").map(|_| child.num("opacity")).transpose()?.unwrap_or(1.),
+                x: child.num("offsetx").unwrap_or(0),
+                y: child.num("offsety").unwrap_or(0),
+                kind,
+            });
+        }
+        Ok(layers)
+    }
+
+    fn to_map(&self) -> Result<TiledMap, TiledError> {
+        if self.name != "map" {
+            return Err(TiledError::Parse(format!("root element is <{}>, not <map>", self.name)));
+        }
+
+        let tilesets = self.children("tileset")
+            .map(|t| Ok(Tileset {
+                firstgid: t.num("firstgid")?,
+                source: t.attributes.get("source").cloned(),
+                name: t.attr("name").to_string(),
+                tilewidth: t.num("tilewidth")?,
+                tileheight: t.num("tileheight")?,
+                tilecount: t.num("tilecount")?,
+                columns: t.num("columns")?,
+                tiles: t.children("tile")
+                    .map(|tile| Ok(TilesetTile {
+                        id: tile.num("id")?,
+                        kind: tile.attr("type").to_string(),
+                        class: tile.attr("class").to_string(),
+                        properties: tile.properties(),
+                    }))
+                    .collect::<Result<_, TiledError>>()?,
+                ..Default::default()
+            }))
+            .collect::<Result<_, TiledError>>()?;
+
+        Ok(TiledMap {
+            kind: "map".to_string(),
+            version: self.attr("version").to_string(),
+            orientation: self.attr("orientation").to_string(),
+            renderorder: self.attr("renderorder").to_string(),
+            width: self.num("width")?,
+            height: self.num("height")?,
+            tilewidth: self.num("tilewidth")?,
+            tileheight: self.num("tileheight")?,
+            infinite: self.attr("infinite") == "1",
+            nextlayerid: self.num("nextlayerid")?,
+            nextobjectid: self.num("nextobjectid")?,
+            layers: self.layers()?,
+            tilesets,
+        })
+    }
+}
+
+fn write_properties(out: &mut String, indent: &str, properties: &[Property]) {
+    if properties.is_empty() { return; }
+    out.push_str(&format!("{indent}<properties>\n"));
+    for p in properties {
+        out.push_str(&format!(
+            "{indent} <property name=\"{}\" value=\"{}\"/>\n",
+            escape_str_attribute(&p.name), escape_str_attribute(&p.value_string()),
+        ));
+    }
+    out.push_str(&format!("{indent}</properties>\n"));
+}
+
+/// Writes the map as TMX. Only handles what [TiledMap::from_build_data] produces.
+fn write_tmx(map: &TiledMap) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<map version=\"{}\" orientation=\"{}\" renderorder=\"{}\" width=\"{}\" height=\"{}\" \
+         tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+        map.version, map.orientation, map.renderorder, map.width, map.height,
+        map.tilewidth, map.tileheight, map.nextlayerid, map.nextobjectid,
+    ));
+
+    for t in map.tilesets.iter() {
+        out.push_str(&format!(
+            " <tileset firstgid=\"{}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n",
+            t.firstgid, escape_str_attribute(&t.name), t.tilewidth, t.tileheight, t.tilecount, t.columns,
+        ));
+        out.push_str(&format!(
+            "  <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            escape_str_attribute(&t.image), t.imagewidth, t.imageheight,
+        ));
+        for tile in t.tiles.iter() {
+            out.push_str(&format!("  <tile id=\"{}\">\n", tile.id));
+            write_properties(&mut out, "   ", &tile.properties);
+            out.push_str("  </tile>\n");
+        }
+        out.push_str(" </tileset>\n");
+    }
+
+    for layer in map.layers.iter() {
+        let name = escape_str_attribute(&layer.name);
+        match &layer.kind {
+            LayerKind::TileLayer { width, height, data: LayerData::Gids(gids), .. } => {
+                out.push_str(&format!(" <layer id=\"{}\" name=\"{name}\" width=\"{width}\" height=\"{height}\">\n", layer.id));
+                out.push_str("  <data encoding=\"csv\">\n");
+                let rows = gids.chunks(*width as usize)
+                    .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                    .collect::<Vec<_>>();
+                out.push_str(&rows.join(",\n"));
+                out.push_str("\n  </data>\n </layer>\n");
+            },
+            LayerKind::ObjectGroup { objects } => {
+                out.push_str(&format!(" <objectgroup id=\"{}\" name=\"{name}\">\n", layer.id));
+                for o in objects {
+                    out.push_str(&format!(
+                        "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\"",
+                        o.id, escape_str_attribute(&o.name), escape_str_attribute(&o.kind), o.x, o.y,
+                    ));
+                    if o.width > 0. || o.height > 0. {
+                        out.push_str(&format!(" width=\"{}\" height=\"{}\"", o.width, o.height));
+                    }
+                    out.push_str(">\n");
+                    write_properties(&mut out, "   ", &o.properties);
+                    if o.point {
+                        out.push_str("   <point/>\n");
+                    }
+                    out.push_str("  </object>\n");
+                }
+                out.push_str(" </objectgroup>\n");
+            },
+            _ => {},
+        }
+    }
+    out.push_str("</map>\n");
+    out
+}
+
+fn is_tmx(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tmx"))
+}
+
+/// Whether `path` looks like a Tiled map, going by its extension.
+pub fn is_tiled_path(path: &Path) -> bool {
+    is_tmx(path) || path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tmj") || e.eq_ignore_ascii_case("json"))
+}
+
+/// Parses a Tiled map in the JSON format into a [Prefab].
+pub fn parse_json(text: &str, depth: u32) -> Result<Prefab, TiledError> {
+    let map: TiledMap = serde_json::from_str(text).map_err(|e| TiledError::Parse(e.to_string()))?;
+    map.to_prefab(depth)
+}
+
+/// Parses a Tiled map in the TMX format into a [Prefab].
+pub fn parse_tmx(text: &str, depth: u32) -> Result<Prefab, TiledError> {
+    Element::parse(text)?.to_map()?.to_prefab(depth)
+}
+
+/// Writes the board, the rooms, the starting position and the hand-placed monsters
+/// as a Tiled map in the JSON format.
+pub fn to_json(build_data: &BuildData) -> String {
+    // Serializing our own structs can't fail.
+    serde_json::to_string_pretty(&TiledMap::from_build_data(build_data)).unwrap()
+}
+
+/// Like [to_json], in the TMX format.
+pub fn to_tmx(build_data: &BuildData) -> String {
+    write_tmx(&TiledMap::from_build_data(build_data))
+}
+
+/// The tileset used by exported maps, one solid square per [Tile] in the colours of
+/// [export::to_image].
+pub fn tileset_image() -> RgbImage {
+    let count = prefab::TILE_NAMES.len() as u32;
+    RgbImage::from_fn(TILE_SIZE * count, TILE_SIZE, |x, _| {
+        export::tile_color(prefab::TILE_NAMES[(x / TILE_SIZE) as usize].0)
+    })
+}
+
+/// Reads and builds the Tiled map at `path`, TMX if the extension is `.tmx` and
+/// JSON otherwise. See [prefab::build].
+pub fn load(path: &Path, depth: u32) -> Result<BuildData, TiledError> {
+    let text = std::fs::read_to_string(path).map_err(|e| TiledError::Io(e.to_string()))?;
+    let prefab = if is_tmx(path) { parse_tmx(&text, depth)? } else { parse_json(&text, depth)? };
+    prefab::build(prefab, depth).map_err(TiledError::Invalid)
+}
+
+/// Writes `build_data` to `path` as a Tiled map, TMX if the extension is `.tmx` and
+/// JSON otherwise. The [tileset image](TILESET_IMAGE) is written next to it.
+pub fn save(path: &Path, build_data: &BuildData) -> Result<(), TiledError> {
+    let text = if is_tmx(path) { to_tmx(build_data) } else { to_json(build_data) };
+    std::fs::write(path, text).map_err(|e| TiledError::Io(e.to_string()))?;
+
+    let image_path = path.with_file_name(TILESET_IMAGE);
+    if !image_path.exists() {
+        tileset_image().save(&image_path).map_err(|e| TiledError::Io(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapgen::named_builder;
+
+    /// A 4x3 map with a floor in the middle, its tiles in `data`.
+    fn tmx(data: &str, objects: &str) -> String {
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="3" tilewidth="16" tileheight="16" infinite="0" nextlayerid="3" nextobjectid="4">
+ <tileset firstgid="1" name="test" tilewidth="16" tileheight="16" tilecount="3" columns="3">
+  <tile id="0" class="Floor"/>
+  <tile id="1" type="Wall"/>
+  <tile id="2">
+   <properties>
+    <property name="tile" value="DownStairs"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="tiles" width="4" height="3">
+  {data}
+ </layer>
+ <objectgroup id="2" name="objects">
+  {objects}
+ </objectgroup>
+</map>"#)
+    }
+
+    fn rect_tuple(r: &Rect) -> (u32, u32, u32, u32) {
+        (r.x1, r.y1, r.x2, r.y2)
+    }
+
+    fn assert_same(prefab: &Prefab, build_data: &BuildData) {
+        assert_eq!(prefab.board.get_tiles_cloned(), build_data.board.get_tiles_cloned());
+        assert_eq!(prefab.starting_position, build_data.starting_position);
+        assert_eq!(
+            prefab.rects.iter().flatten().map(rect_tuple).collect::<Vec<_>>(),
+            build_data.rects.iter().flatten().map(rect_tuple).collect::<Vec<_>>(),
+        );
+        assert_eq!(&prefab.spawns, build_data.spawns.as_ref().unwrap_or(&Vec::new()));
+    }
+
+    #[test]
+    fn round_trip() {
+        for name in ["simple_rooms", "bsp"] {
+            let build_data = named_builder(name, 3, 1).unwrap();
+            assert!(build_data.rects.as_ref().is_some_and(|r| !r.is_empty()), "{name} has no rooms");
+
+            assert_same(&parse_tmx(&to_tmx(&build_data), 1).unwrap(), &build_data);
+            assert_same(&parse_json(&to_json(&build_data), 1).unwrap(), &build_data);
+        }
+    }
+
+    #[test]
+    fn csv_data() {
+        let prefab = parse_tmx(&tmx(r#"<data encoding="csv">
+2,2,2,2,
+2,1,3,2,
+0,2,2,2
+</data>"#, ""), 0).unwrap();
+
+        let board = &prefab.board;
+        // The first row is the top one and empty tiles are walls.
+        assert_eq!(board.get_tile_xy(1, 1), Tile::Floor);
+        assert_eq!(board.get_tile_xy(2, 1), Tile::DownStairs);
+        assert_eq!(board.get_tile_xy(0, 0), Tile::Wall);
+        assert_eq!(board.get_tiles_cloned().iter().filter(|t| **t == Tile::Wall).count(), 10);
+        assert_eq!(prefab.starting_position, None);
+        assert!(prefab.rects.is_none());
+    }
+
+    #[test]
+    fn tile_data() {
+        let tiles = [2, 2, 2, 2, 2, 1, 1, 2, 2, 2, 2, 3].map(|gid| format!("<tile gid=\"{gid}\"/>"));
+        let prefab = parse_tmx(&tmx(&format!("<data>{}</data>", tiles.join("")), ""), 0).unwrap();
+
+        let board = &prefab.board;
+        assert_eq!(board.get_tile_xy(1, 1), Tile::Floor);
+        assert_eq!(board.get_tile_xy(2, 1), Tile::Floor);
+        assert_eq!(board.get_tile_xy(3, 0), Tile::DownStairs);
+        assert_eq!(board.get_tile_xy(0, 2), Tile::Wall);
+    }
+
+    #[test]
+    fn objects() {
+        let data = r#"<data encoding="csv">2,2,2,2,2,1,1,2,2,2,2,2</data>"#;
+        let objects = r#"<object id="1" class="start" x="24" y="24"><point/></object>
+  <object id="2" type="spawn" name="Rat" x="40" y="24"><point/></object>
+  <object id="3" class="spawn" gid="1" x="16" y="32" width="16" height="16">
+   <properties>
+    <property name="monster" value="Goblin"/>
+   </properties>
+  </object>
+  <object id="4" class="room" x="16" y="16" width="32" height="16"/>
+  <object id="5" class="note" name="ignored" x="0" y="0"/>"#;
+        let prefab = parse_tmx(&tmx(data, objects), 0).unwrap();
+
+        assert_eq!(prefab.starting_position, Some(Point::new(1, 1)));
+        // Tile objects are anchored at their bottom left corner.
+        assert_eq!(prefab.spawns, vec![
+            (Point::new(2, 1), "Rat".to_string()),
+            (Point::new(1, 1), "Goblin".to_string()),
+        ]);
+        assert_eq!(prefab.rects.iter().flatten().map(rect_tuple).collect::<Vec<_>>(), vec![(1, 1, 2, 1)]);
+
+        let second_start = objects.replace("type=\"spawn\"", "class=\"start\"");
+        assert!(matches!(parse_tmx(&tmx(data, &second_start), 0), Err(TiledError::MultipleStarts { id: 2 })));
+    }
+}
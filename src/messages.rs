@@ -0,0 +1,147 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use bevy::prelude::*;
+
+use crate::{mapgen::BuildData, pieces::components::Piece, player::Player, state::MainState};
+
+/// Older messages are dropped once the log is this long.
+const MAX_MESSAGES: usize = 500;
+
+pub struct MessagesPlugin;
+
+impl Plugin for MessagesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MessageLog>()
+            .add_systems(OnEnter(MainState::Game), announce_level);
+    }
+}
+
+/// What a [Message] is about, which decides its colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    Info,
+    /// Something the player tried that didn't work.
+    Warning,
+    /// The player attacking.
+    Attack,
+    /// The player being attacked.
+    Hurt,
+    Death,
+    Loot,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub kind: MessageKind,
+    pub text: String,
+    /// How many times in a row this message was logged.
+    pub count: u32,
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.count {
+            1 => write!(f, "{}", self.text),
+            n => write!(f, "{} x{n}", self.text),
+        }
+    }
+}
+
+/// Bevy [Resource] with the messages shown to the player, oldest first. A message
+/// that is the same as the last one only increases its [Message::count].
+#[derive(Resource, Default, Debug)]
+pub struct MessageLog(VecDeque<Message>);
+
+impl MessageLog {
+    pub fn push(&mut self, kind: MessageKind, text: impl Into<String>) {
+        let text = text.into();
+        info!("{text}");
+
+        if let Some(last) = self.0.back_mut().filter(|m| m.kind == kind && m.text == text) {
+            last.count += 1;
+            return;
+        }
+        if self.0.len() == MAX_MESSAGES {
+            self.0.pop_front();
+        }
+        self.0.push_back(Message { kind, text, count: 1 });
+    }
+
+    /// For [Action](crate::actions::Action)s, which only have the [World]. Does
+    /// nothing if there is no log.
+    pub fn push_to(world: &mut World, kind: MessageKind, text: impl Into<String>) {
+        if let Some(mut log) = world.get_resource_mut::<MessageLog>() {
+            log.push(kind, text);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> + ExactSizeIterator {
+        self.0.iter()
+    }
+}
+
+/// How messages refer to the [Entity]: "you" for the player, "the goblin" otherwise.
+pub fn name_of(world: &World, entity: Entity) -> String {
+    if world.get::<Player>(entity).is_some() {
+        return "you".to_string();
+    }
+    let kind = world.get::<Piece>(entity).map(|p| p.kind.as_str()).unwrap_or_default();
+    format!("the {kind}")
+}
+
+/// `text` starting with an upper case letter, e.g. a [name_of] at the start of a sentence.
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
+fn announce_level(mut log: ResMut<MessageLog>, build_data: Res<BuildData>) {
+    log.push(MessageKind::Info, format!("You enter depth {}.", build_data.depth));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(log: &MessageLog) -> Vec<String> {
+        log.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn repeats_collapse() {
+        let mut log = MessageLog::default();
+        for _ in 0..3 {
+            log.push(MessageKind::Attack, "You hit the rat.");
+        }
+        log.push(MessageKind::Info, "You hit the rat.");
+        log.push(MessageKind::Info, "The rat dies.");
+        log.push(MessageKind::Info, "You hit the rat.");
+
+        assert_eq!(texts(&log), ["You hit the rat. x3", "You hit the rat.", "The rat dies.", "You hit the rat."]);
+        assert_eq!(log.iter().next().unwrap().count, 3);
+    }
+
+    #[test]
+    fn oldest_are_dropped() {
+        let mut log = MessageLog::default();
+        for i in 0..MAX_MESSAGES + 10 {
+            log.push(MessageKind::Info, format!("message {i}"));
+        }
+        assert_eq!(log.len(), MAX_MESSAGES);
+        assert_eq!(log.iter().next().unwrap().text, "message 10");
+        assert_eq!(log.iter().last().unwrap().text, format!("message {}", MAX_MESSAGES + 9));
+
+        // A repeat doesn't take up room.
+        log.push(MessageKind::Info, format!("message {}", MAX_MESSAGES + 9));
+        assert_eq!(log.len(), MAX_MESSAGES);
+        assert_eq!(log.iter().next().unwrap().text, "message 10");
+    }
+}
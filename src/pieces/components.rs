@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::actions::Action;
+use crate::{actions::Action, random::Dice};
 
 #[derive(Component)]
 pub struct Piece {
@@ -14,14 +14,53 @@ pub struct Piece {
 #[derive(Component, Default)]
 pub struct Actor(pub Vec<(Box<dyn Action>, i32)>);
 
+/// Index into the sprite sheet used to draw a [Piece]. Pieces without a [Glyph]
+/// are drawn based on their `kind`.
+#[derive(Component)]
+pub struct Glyph(pub usize);
+
 #[derive(Component)]
 pub struct Health {
     pub value: u32,
+    pub max: u32,
 }
 
-#[derive(Component)]
+impl Health {
+    /// Full health.
+    pub fn new(max: u32) -> Self {
+        Self { value: max, max }
+    }
+}
+
+/// How much [Energy] an [Actor] gains per tick of game time.
+/// [NORMAL_SPEED](crate::config::time::NORMAL_SPEED) is the player's speed.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Speed(pub u32);
+
+/// An [Actor] can act once it has [READY_ENERGY](crate::config::time::READY_ENERGY),
+/// and each [Action] it takes costs energy. Can go negative after a costly action.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Energy(pub i32);
+
+/// Combat stats, see [combat](crate::combat).
+#[derive(Component, Clone, Debug)]
 pub struct Fighter {
+    /// Half of it is added to the damage.
     pub strength: u32,
+    /// Bonus to hit.
+    pub attack: i32,
+    /// Makes the piece harder to hit.
+    pub defense: i32,
+    /// Subtracted from the damage of every hit.
+    pub armor: u32,
+    pub damage: Dice,
+}
+
+/// Which side a piece is on. Pieces of the same faction don't attack each other.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Monsters,
 }
 
 /// Marker component indicating whether a [Tile][crate::board::components::Tile]
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, ecs::query::Has};
+
+use crate::{board::components::Position, mapgen::BuildData, messages::{MessageKind, MessageLog}, player::Player, random::{RngStream, Rngs}, state::MainState, GameSeed};
+
+use super::{components::{Glyph, Piece}, spawn_table::DropEntry};
+
+/// Sprite sheet index of a corpse (`%`).
+const CORPSE_GLYPH: usize = 37;
+
+/// Sent when an [Entity]'s [Health](super::components::Health) reaches zero, see
+/// [DamageAction](crate::actions::models::DamageAction). By then it is [Dead].
+#[derive(Event, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    /// Whoever dealt the killing blow, if anyone.
+    pub killer: Option<Entity>,
+}
+
+/// Marks a piece that has died but hasn't been cleaned up by [handle_deaths] yet.
+/// It can no longer act, block or be attacked.
+#[derive(Component)]
+pub struct Dead;
+
+/// What's left of a dead monster. Doesn't block movement.
+#[derive(Component)]
+pub struct Corpse;
+
+/// Something lying on the floor, e.g. a monster's drop.
+#[derive(Component)]
+pub struct Item;
+
+/// What a monster may leave behind when it dies, see [DropEntry].
+#[derive(Component, Clone, Default)]
+pub struct Drops(pub Vec<DropEntry>);
+
+/// Number of kills of a piece, by the kind of the piece killed.
+#[derive(Component, Clone, Default, Debug)]
+pub struct Kills(pub BTreeMap<String, u32>);
+
+impl Kills {
+    pub fn total(&self) -> u32 {
+        self.0.values().sum()
+    }
+}
+
+/// Bevy [Resource] describing the player's death, inserted when the game is over.
+#[derive(Resource, Clone, Debug)]
+pub struct GameOverSummary {
+    pub seed: u64,
+    pub depth: u32,
+    /// Kind of the piece that killed the player.
+    pub killed_by: Option<String>,
+    pub kills: Kills,
+}
+
+/// What [handle_deaths] needs to know about the dead and their killers.
+type DeathQuery<'a> = (&'a Piece, &'a Position, Option<&'a Drops>, Option<&'a mut Kills>, Has<Player>);
+
+/// Turns dead monsters into [Corpse]s and their [Drops], and credits each kill to the
+/// killer's [Kills]. The player's death is left to [end_game].
+pub fn handle_deaths(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    mut query: Query<DeathQuery>,
+    mut rngs: ResMut<Rngs>,
+    mut log: ResMut<MessageLog>,
+) {
+    for ev in ev_death.iter() {
+        let Ok((piece, pos, drops, _, is_player)) = query.get(ev.entity) else { continue };
+        let (kind, p) = (piece.kind.clone(), pos.p);
+        let drops = drops.cloned();
+
+        if let Some((_, _, _, Some(mut killer_kills), _)) = ev.killer.and_then(|killer| query.get_mut(killer).ok()) {
+            *killer_kills.0.entry(kind.clone()).or_default() += 1;
+        }
+        if is_player { continue; }
+
+        log.push(MessageKind::Death, format!("The {kind} dies."));
+        commands.entity(ev.entity).despawn_recursive();
+        commands.spawn((
+            Piece { kind: format!("{kind} corpse") },
+            Glyph(CORPSE_GLYPH),
+            Position { p },
+            Corpse,
+        ));
+
+        let Some(drops) = drops else { continue };
+        let rng = rngs.stream(RngStream::Loot);
+        for drop in drops.0.iter().filter(|d| rng.gen_bool(d.chance.clamp(0., 1.))) {
+            log.push(MessageKind::Loot, format!("The {kind} drops {}.", drop.name));
+            commands.spawn((
+                Piece { kind: drop.name.clone() },
+                Glyph(drop.glyph),
+                Position { p },
+                Item,
+            ));
+        }
+    }
+}
+
+/// Ends the game when the player dies, with a [GameOverSummary]. Runs after
+/// [handle_deaths] so that kills made in the same turn are counted.
+pub fn end_game(
+    mut commands: Commands,
+    mut ev_death: EventReader<DeathEvent>,
+    query: Query<(&Piece, Option<&Kills>, Has<Player>)>,
+    build_data: Res<BuildData>,
+    game_seed: Res<GameSeed>,
+    mut log: ResMut<MessageLog>,
+    mut next_state: ResMut<NextState<MainState>>,
+) {
+    for ev in ev_death.iter() {
+        let Ok((_, kills, true)) = query.get(ev.entity) else { continue };
+
+        log.push(MessageKind::Death, "You die...");
+        commands.insert_resource(GameOverSummary {
+            seed: game_seed.0,
+            depth: build_data.depth,
+            killed_by: ev.killer.and_then(|killer| query.get(killer).ok()).map(|(piece, _, _)| piece.kind.clone()),
+            kills: kills.cloned().unwrap_or_default(),
+        });
+        next_state.set(MainState::GameOver);
+    }
+}
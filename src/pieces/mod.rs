@@ -1,36 +1,129 @@
+use std::collections::BTreeMap;
+
 use bevy::prelude::*;
 
-use crate::{board::components::Position, point::Point, state::MainState};
+use crate::{board::components::{Position, Tile}, point::Point, state::MainState, mapgen::{MapGenSet, BuildData}, random::{RngStream, Rngs}, theme::ActiveTheme};
 
-use self::components::{Actor, Piece, Walker, Fighter, TileOccupier};
+use self::{components::{Actor, Piece, Walker, Fighter, TileOccupier, Health, Glyph, Speed, Energy, Faction}, death::{DeathEvent, Drops, Kills}, spawn_table::{MonsterEntry, SpawnTables}, status::{Inflicts, StatusEffects}};
 
 pub mod components;
+pub mod death;
+pub mod spawn_table;
+pub mod status;
+
+/// Maps without rooms (e.g. caves) are split into square regions of this size
+/// when spawning monsters.
+const SPAWN_REGION_SIZE: i32 = 16;
 
 pub struct PiecesPlugin;
 
 impl Plugin for PiecesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(MainState::Game), spawn_npcs);
+        app.init_resource::<SpawnTables>()
+            .add_event::<DeathEvent>()
+            .add_systems(OnEnter(MainState::Game), spawn_monsters.in_set(MapGenSet::Spawning))
+            .add_systems(Update, (death::handle_deaths, death::end_game)
+                .chain()
+                .run_if(on_event::<DeathEvent>()));
     }
 }
 
-pub fn spawn_npcs(mut commands: Commands) {
-    commands.spawn((
-        Actor::default(),
-        Piece { kind: "NPC".to_string() },
-        Position { p: Point::new(3, 5) },
-        Walker,
-        Fighter { strength: 2 },
-        TileOccupier {},
-    ));
+/// Groups the free floor tiles of the map into the areas monsters are spawned in:
+/// one per room, or one per [SPAWN_REGION_SIZE] square if there are no rooms.
+/// The area containing the starting position is left out.
+fn spawn_areas(build_data: &BuildData) -> Vec<Vec<Point>> {
+    let board = &build_data.board;
+    let is_free = |p: &Point| {
+        let i = board.xy_to_index(p.x as u32, p.y as u32);
+        board.get_tile(i) == Tile::Floor
+            && Some(*p) != build_data.starting_position
+            && build_data.distances.as_ref().is_none_or(|d| d[i].is_some())
+    };
+
+    let areas = match build_data.rects {
+        Some(ref rooms) => rooms.iter()
+            .filter(|r| !build_data.starting_position.is_some_and(|s| r.contains(s)))
+            .map(|r| r.iter_xy().map(Point::from).filter(is_free).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        None => {
+            let start_region = build_data.starting_position
+                .map(|s| (s.x / SPAWN_REGION_SIZE, s.y / SPAWN_REGION_SIZE));
+            let mut regions: BTreeMap<(i32, i32), Vec<Point>> = BTreeMap::new();
+            for p in board.iter_points().filter(is_free) {
+                let key = (p.x / SPAWN_REGION_SIZE, p.y / SPAWN_REGION_SIZE);
+                if Some(key) != start_region {
+                    regions.entry(key).or_default().push(p);
+                }
+            }
+            regions.into_values().collect()
+        },
+    };
+    areas.into_iter().filter(|a| !a.is_empty()).collect()
+}
+
+fn spawn_monster(commands: &mut Commands, monster: &MonsterEntry, p: Point) {
     commands.spawn((
         Actor::default(),
-        Piece { kind: "NPC".to_string() },
-        Position { p: Point::new(5, 5) },
+        Piece { kind: monster.name.clone() },
+        Glyph(monster.glyph),
+        Position { p },
         Walker,
-        Fighter { strength: 3 },
+        Health::new(monster.health),
+        Fighter {
+            strength: monster.strength,
+            attack: monster.attack,
+            defense: monster.defense,
+            armor: monster.armor,
+            damage: monster.damage,
+        },
         TileOccupier {},
+        Speed(monster.speed),
+        Energy::default(),
+        Faction::Monsters,
+        Drops(monster.drops.clone()),
+        Kills::default(),
+        StatusEffects::default(),
+        Inflicts(monster.inflicts.clone()),
     ));
 }
 
+/// Populates the map with monsters picked from the [SpawnTable](spawn_table::SpawnTable)
+/// of the [ActiveTheme], or with the ones in [BuildData::spawns] if there are any.
+pub fn spawn_monsters(
+    mut commands: Commands,
+    build_data: Res<BuildData>,
+    spawn_tables: Res<SpawnTables>,
+    theme: Res<ActiveTheme>,
+    mut rngs: ResMut<Rngs>,
+) {
+    let Some(spawn_table) = spawn_tables.0.get(&theme.0.spawn_table) else {
+        error!("theme {} uses unknown spawn table {}", theme.0.name, theme.0.spawn_table);
+        return;
+    };
+
+    if let Some(spawns) = &build_data.spawns {
+        for (p, name) in spawns {
+            // Look in the theme's table first, so that its version of a monster wins.
+            let monster = std::iter::once(spawn_table)
+                .chain(spawn_tables.0.values())
+                .find_map(|table| table.monsters.iter().find(|m| m.name == *name));
+            match monster {
+                Some(monster) => spawn_monster(&mut commands, monster, *p),
+                None => warn!("unknown monster {name} at ({}, {})", p.x, p.y),
+            }
+        }
+        return;
+    }
 
+    let rng = rngs.stream(RngStream::Spawning);
+
+    for mut area in spawn_areas(&build_data) {
+        let count = rng.gen_range(0..=spawn_table.max_per_room);
+        for _ in 0..count {
+            if area.is_empty() { break; }
+            let p = area.swap_remove(rng.gen_range(0..area.len()));
+            let Some(monster) = spawn_table.roll(build_data.depth, rng) else { return };
+            spawn_monster(&mut commands, monster, p);
+        }
+    }
+}
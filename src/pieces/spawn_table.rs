@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{random::{Dice, PRng}, config};
+
+use super::status::InflictEntry;
+
+const SPAWN_TABLES: &str = include_str!("../../assets/data/spawn_tables.ron");
+
+/// A monster type that can be spawned, see `assets/data/spawn_tables.ron`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MonsterEntry {
+    pub name: String,
+    pub glyph: usize,
+    pub health: u32,
+    pub strength: u32,
+    pub weight: u32,
+    pub min_depth: u32,
+    pub max_depth: u32,
+    /// See [Speed](super::components::Speed).
+    #[serde(default = "default_speed")]
+    pub speed: u32,
+    /// See [Fighter](super::components::Fighter).
+    #[serde(default)]
+    pub attack: i32,
+    #[serde(default)]
+    pub defense: i32,
+    #[serde(default)]
+    pub armor: u32,
+    #[serde(default)]
+    pub damage: Dice,
+    #[serde(default)]
+    pub drops: Vec<DropEntry>,
+    /// Status effects its hits may cause.
+    #[serde(default)]
+    pub inflicts: Vec<InflictEntry>,
+}
+
+/// Something a monster leaves behind when it dies, with the given `chance` (0 to 1).
+#[derive(Deserialize, Debug, Clone)]
+pub struct DropEntry {
+    pub name: String,
+    pub glyph: usize,
+    pub chance: f64,
+}
+
+fn default_speed() -> u32 {
+    config::time::NORMAL_SPEED
+}
+
+/// The monsters that can be spawned on a level and how often.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpawnTable {
+    pub max_per_room: u32,
+    pub monsters: Vec<MonsterEntry>,
+}
+
+/// Bevy [Resource] with every [SpawnTable] by name. Each [Theme](crate::theme::Theme)
+/// picks one of them.
+#[derive(Resource, Debug, Clone)]
+pub struct SpawnTables(pub BTreeMap<String, SpawnTable>);
+
+impl Default for SpawnTables {
+    fn default() -> Self {
+        let tables = ron::from_str(SPAWN_TABLES)
+            .unwrap_or_else(|e| panic!("invalid spawn tables: {e}"));
+        Self(tables)
+    }
+}
+
+impl SpawnTable {
+    /// Pick a random [MonsterEntry] that can appear at `depth`, weighted by
+    /// [MonsterEntry::weight]. Returns `None` if nothing can spawn at that depth.
+    pub fn roll(&self, depth: u32, rng: &mut PRng) -> Option<&MonsterEntry> {
+        let eligible = self.monsters.iter()
+            .filter(|m| m.weight > 0 && (m.min_depth..=m.max_depth).contains(&depth))
+            .collect::<Vec<_>>();
+        rng.choose_weighted(&eligible, |m| m.weight).copied()
+    }
+}
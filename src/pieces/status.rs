@@ -0,0 +1,340 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{actions::{Action, models::{DamageAction, HealAction}}, messages::{self, MessageKind, MessageLog}, player::Player};
+
+/// Longest a status effect can be extended to, in turns.
+const MAX_TURNS: u32 = 20;
+/// Strongest a status effect can get by stacking.
+const MAX_POTENCY: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum StatusKind {
+    /// Deals `potency` damage every turn.
+    Poison,
+    /// Heals `potency` every turn.
+    Regeneration,
+    /// Skips every turn.
+    Stun,
+    /// Doubles the [Speed](super::components::Speed).
+    Haste,
+    /// Halves the [Speed](super::components::Speed).
+    Slow,
+    /// Walks in random directions and doesn't attack.
+    Confusion,
+    /// Deals `potency` damage every turn.
+    Burning,
+}
+
+/// What happens when a piece gets a [StatusKind] it already has.
+enum Stacking {
+    /// The longer duration and the higher potency win.
+    Refresh,
+    /// The durations add up.
+    Extend,
+    /// The potencies add up, the longer duration wins.
+    Intensify,
+}
+
+impl StatusKind {
+    fn stacking(self) -> Stacking {
+        match self {
+            StatusKind::Poison => Stacking::Intensify,
+            StatusKind::Confusion => Stacking::Extend,
+            StatusKind::Regeneration | StatusKind::Stun | StatusKind::Haste
+                | StatusKind::Slow | StatusKind::Burning => Stacking::Refresh,
+        }
+    }
+
+    /// The status that is cancelled by this one.
+    fn opposite(self) -> Option<StatusKind> {
+        match self {
+            StatusKind::Haste => Some(StatusKind::Slow),
+            StatusKind::Slow => Some(StatusKind::Haste),
+            _ => None,
+        }
+    }
+
+    /// As in "you are poisoned".
+    pub fn adjective(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "poisoned",
+            StatusKind::Regeneration => "regenerating",
+            StatusKind::Stun => "stunned",
+            StatusKind::Haste => "hasted",
+            StatusKind::Slow => "slowed",
+            StatusKind::Confusion => "confused",
+            StatusKind::Burning => "burning",
+        }
+    }
+}
+
+impl Display for StatusKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", messages::capitalize(self.adjective()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Turns of the affected piece left.
+    pub turns: u32,
+    pub potency: u32,
+    /// Whoever caused it, credited with kills by poison or fire.
+    pub source: Option<Entity>,
+}
+
+/// The status effects on a piece, each of a different [StatusKind]. They tick at the
+/// end of each of the piece's turns, see [tick].
+#[derive(Component, Clone, Default, Debug)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|e| e.kind == kind)
+    }
+
+    /// Adds the effect, stacking it with one of the same kind (see [Stacking]) and
+    /// cancelling its opposite.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        if let Some(opposite) = effect.kind.opposite() {
+            self.0.retain(|e| e.kind != opposite);
+        }
+
+        let Some(existing) = self.0.iter_mut().find(|e| e.kind == effect.kind) else {
+            self.0.push(StatusEffect { turns: effect.turns.min(MAX_TURNS), ..effect });
+            return;
+        };
+        match effect.kind.stacking() {
+            Stacking::Refresh => {
+                existing.turns = existing.turns.max(effect.turns);
+                existing.potency = existing.potency.max(effect.potency);
+            },
+            Stacking::Extend => {
+                existing.turns += effect.turns;
+                existing.potency = existing.potency.max(effect.potency);
+            },
+            Stacking::Intensify => {
+                existing.turns = existing.turns.max(effect.turns);
+                existing.potency = (existing.potency + effect.potency).min(MAX_POTENCY);
+            },
+        }
+        existing.turns = existing.turns.min(MAX_TURNS);
+        existing.source = effect.source.or(existing.source);
+    }
+
+    /// `speed` changed by [Haste](StatusKind::Haste) or [Slow](StatusKind::Slow). Slowed
+    /// pieces still get to act now and then.
+    pub fn speed(&self, speed: u32) -> u32 {
+        if self.has(StatusKind::Haste) {
+            speed * 2
+        } else if self.has(StatusKind::Slow) {
+            (speed / 2).max(1)
+        } else {
+            speed
+        }
+    }
+}
+
+/// A status effect a monster may cause when it hits, see `assets/data/spawn_tables.ron`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InflictEntry {
+    pub status: StatusKind,
+    pub turns: u32,
+    #[serde(default = "default_potency")]
+    pub potency: u32,
+    /// Chance (0 to 1) of causing it with a hit.
+    pub chance: f64,
+}
+
+fn default_potency() -> u32 {
+    1
+}
+
+/// What status effects a piece causes with its hits.
+#[derive(Component, Clone, Default)]
+pub struct Inflicts(pub Vec<InflictEntry>);
+
+/// Ticks the [StatusEffects] of the [Entity] at the end of its turn: damage and
+/// healing are returned as follow-up actions, then every effect loses a turn and
+/// the ones that ran out are removed.
+pub fn tick(world: &mut World, entity: Entity) -> Vec<Box<dyn Action>> {
+    let Some(effects) = world.get::<StatusEffects>(entity).filter(|e| !e.0.is_empty()) else {
+        return Vec::new()
+    };
+    let effects = effects.0.clone();
+
+    let is_player = world.get::<Player>(entity).is_some();
+    let name = messages::capitalize(&messages::name_of(world, entity));
+    let kind = if is_player { MessageKind::Hurt } else { MessageKind::Info };
+
+    let mut actions = Vec::new();
+    for effect in effects.iter() {
+        match effect.kind {
+            StatusKind::Poison => {
+                let s = if is_player { "" } else { "s" };
+                MessageLog::push_to(world, kind, format!("{name} suffer{s} {} poison damage.", effect.potency));
+                actions.push(Box::new(DamageAction::new(entity, effect.potency, effect.source)) as Box<dyn Action>);
+            },
+            StatusKind::Burning => {
+                let s = if is_player { "" } else { "s" };
+                MessageLog::push_to(world, kind, format!("{name} burn{s} for {}.", effect.potency));
+                actions.push(Box::new(DamageAction::new(entity, effect.potency, effect.source)));
+            },
+            StatusKind::Regeneration => actions.push(Box::new(HealAction::new(entity, effect.potency))),
+            StatusKind::Stun | StatusKind::Haste | StatusKind::Slow | StatusKind::Confusion => (),
+        }
+        if effect.turns <= 1 && is_player {
+            MessageLog::push_to(world, MessageKind::Info, format!("You are no longer {}.", effect.kind.adjective()));
+        }
+    }
+
+    if let Some(mut effects) = world.get_mut::<StatusEffects>(entity) {
+        for effect in effects.0.iter_mut() {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        effects.0.retain(|e| e.turns > 0);
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::{components::{Health, Piece}, death::DeathEvent};
+
+    fn effect(kind: StatusKind, turns: u32, potency: u32) -> StatusEffect {
+        StatusEffect { kind, turns, potency, source: None }
+    }
+
+    fn applied(effects: &[StatusEffect]) -> StatusEffects {
+        let mut result = StatusEffects::default();
+        for e in effects {
+            result.apply(e.clone());
+        }
+        result
+    }
+
+    fn turns_and_potency(effects: &StatusEffects) -> Vec<(StatusKind, u32, u32)> {
+        effects.0.iter().map(|e| (e.kind, e.turns, e.potency)).collect()
+    }
+
+    #[test]
+    fn refresh() {
+        let effects = applied(&[effect(StatusKind::Stun, 3, 1), effect(StatusKind::Stun, 2, 2)]);
+        assert_eq!(turns_and_potency(&effects), [(StatusKind::Stun, 3, 2)]);
+    }
+
+    #[test]
+    fn extend() {
+        let effects = applied(&[effect(StatusKind::Confusion, 3, 1), effect(StatusKind::Confusion, 4, 1)]);
+        assert_eq!(turns_and_potency(&effects), [(StatusKind::Confusion, 7, 1)]);
+    }
+
+    #[test]
+    fn intensify() {
+        let effects = applied(&[effect(StatusKind::Poison, 5, 1), effect(StatusKind::Poison, 3, 2)]);
+        assert_eq!(turns_and_potency(&effects), [(StatusKind::Poison, 5, 3)]);
+    }
+
+    #[test]
+    fn caps() {
+        assert_eq!(turns_and_potency(&applied(&[effect(StatusKind::Stun, 50, 1)])), [(StatusKind::Stun, MAX_TURNS, 1)]);
+
+        let confusion = applied(&[effect(StatusKind::Confusion, 15, 1), effect(StatusKind::Confusion, 15, 1)]);
+        assert_eq!(turns_and_potency(&confusion), [(StatusKind::Confusion, MAX_TURNS, 1)]);
+
+        let poison = applied(&[effect(StatusKind::Poison, 5, 3), effect(StatusKind::Poison, 5, 3)]);
+        assert_eq!(turns_and_potency(&poison), [(StatusKind::Poison, 5, MAX_POTENCY)]);
+    }
+
+    #[test]
+    fn opposites_cancel() {
+        let effects = applied(&[effect(StatusKind::Poison, 5, 1), effect(StatusKind::Haste, 5, 1), effect(StatusKind::Slow, 3, 1)]);
+        assert_eq!(turns_and_potency(&effects), [(StatusKind::Poison, 5, 1), (StatusKind::Slow, 3, 1)]);
+        assert_eq!(effects.speed(100), 50);
+        assert_eq!(effects.speed(1), 1);
+
+        let effects = applied(&[effect(StatusKind::Slow, 3, 1), effect(StatusKind::Haste, 5, 1)]);
+        assert_eq!(turns_and_potency(&effects), [(StatusKind::Haste, 5, 1)]);
+        assert_eq!(effects.speed(100), 200);
+    }
+
+    #[test]
+    fn source_is_kept() {
+        let mut world = World::new();
+        let (a, b) = (world.spawn_empty().id(), world.spawn_empty().id());
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect { source: Some(a), ..effect(StatusKind::Poison, 3, 1) });
+        effects.apply(effect(StatusKind::Poison, 3, 1));
+        assert_eq!(effects.0[0].source, Some(a));
+        effects.apply(StatusEffect { source: Some(b), ..effect(StatusKind::Poison, 3, 1) });
+        assert_eq!(effects.0[0].source, Some(b));
+    }
+
+    fn world_with(effects: &[StatusEffect]) -> (World, Entity) {
+        let mut world = World::new();
+        world.init_resource::<MessageLog>();
+        world.init_resource::<Events<DeathEvent>>();
+        let entity = world.spawn((
+            Piece { kind: "Rat".to_string() },
+            Health { value: 5, max: 10 },
+            applied(effects),
+        )).id();
+        (world, entity)
+    }
+
+    fn run(world: &mut World, actions: Vec<Box<dyn Action>>) {
+        for action in actions {
+            action.execute(world).unwrap();
+        }
+    }
+
+    #[test]
+    fn tick_expires() {
+        let (mut world, entity) = world_with(&[effect(StatusKind::Stun, 1, 1), effect(StatusKind::Confusion, 2, 1)]);
+
+        assert!(tick(&mut world, entity).is_empty());
+        assert_eq!(turns_and_potency(world.get::<StatusEffects>(entity).unwrap()), [(StatusKind::Confusion, 1, 1)]);
+        assert!(tick(&mut world, entity).is_empty());
+        assert!(world.get::<StatusEffects>(entity).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn tick_damages_and_heals() {
+        let (mut world, entity) = world_with(&[effect(StatusKind::Poison, 2, 2), effect(StatusKind::Regeneration, 3, 3)]);
+
+        let actions = tick(&mut world, entity);
+        assert_eq!(actions.len(), 2);
+        run(&mut world, actions);
+        assert_eq!(world.get::<Health>(entity).unwrap().value, 6);
+        assert_eq!(world.resource::<MessageLog>().iter().next().unwrap().text, "The Rat suffers 2 poison damage.");
+
+        let actions = tick(&mut world, entity);
+        run(&mut world, actions);
+        assert_eq!(world.get::<Health>(entity).unwrap().value, 7);
+        // Only the regeneration is left.
+        let actions = tick(&mut world, entity);
+        run(&mut world, actions);
+        assert_eq!(world.get::<Health>(entity).unwrap().value, 10);
+        assert!(tick(&mut world, entity).is_empty());
+    }
+
+    #[test]
+    fn tick_credits_the_source() {
+        let (mut world, entity) = world_with(&[]);
+        let attacker = world.spawn_empty().id();
+        world.get_mut::<StatusEffects>(entity).unwrap()
+            .apply(StatusEffect { source: Some(attacker), ..effect(StatusKind::Burning, 3, 5) });
+
+        let actions = tick(&mut world, entity);
+        run(&mut world, actions);
+        assert_eq!(world.get::<Health>(entity).unwrap().value, 0);
+        let deaths = world.resource_mut::<Events<DeathEvent>>().drain().collect::<Vec<_>>();
+        assert_eq!(deaths.len(), 1);
+        assert_eq!((deaths[0].entity, deaths[0].killer), (entity, Some(attacker)));
+    }
+}
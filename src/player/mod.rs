@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{state::MainState, pieces::components::{Piece, Actor, Health, TileOccupier, Fighter}, board::components::Position, mapgen::{MapGenSet, BuildData}, camera};
+use crate::{state::MainState, pieces::{components::{Piece, Actor, Health, TileOccupier, Fighter, Speed, Energy, Faction}, death::Kills, status::StatusEffects}, board::components::Position, mapgen::{MapGenSet, BuildData}, camera, config, random::Dice};
 
 pub struct PlayerPlugin;
 
@@ -27,9 +27,21 @@ fn spawn_player(
             Piece { kind: "Player".to_string() },
             // starting position should always be generated when the spawn_player system is run
             Position { p: build_data.starting_position.unwrap() },             
-            Health { value: 10 },
+            Health::new(10),
             TileOccupier {},
-            Fighter { strength: 5 },
+            Fighter {
+                strength: 5,
+                attack: 2,
+                defense: 2,
+                armor: 1,
+                damage: Dice::new(1, 6, 0),
+            },
+            Speed(config::time::NORMAL_SPEED),
+            // the player makes the first move
+            Energy(config::time::READY_ENERGY),
+            Faction::Player,
+            Kills::default(),
+            StatusEffects::default(),
         )
     );
 }
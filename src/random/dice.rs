@@ -0,0 +1,284 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+
+use super::PRng;
+
+/// More dice than this in one expression is most likely a typo.
+const MAX_DICE: u32 = 100;
+
+/// Which of the rolled dice count towards the total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+/// A dice expression like `3d6+2`: roll `count` dice with `sides` sides, keep the
+/// [Keep] ones (`1d20kh1`, `4d6kl3`), multiply their sum by `multiplier` (`2d4*3`)
+/// and add `bonus`. The parts have to come in that order, so `2d4*3+1` is
+/// `(2d4 * 3) + 1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub keep: Option<Keep>,
+    pub multiplier: u32,
+    pub bonus: i32,
+}
+
+/// Why a dice expression couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiceError {
+    Empty,
+    /// There's no `d` between the count and the sides.
+    MissingD(String),
+    /// Expected a number, found this instead.
+    InvalidNumber(String),
+    NoDice,
+    TooManyDice(u32),
+    /// Keeping more dice than are rolled.
+    KeepTooMany { keep: u32, count: u32 },
+    /// Keeping none of the dice (`kh0`).
+    KeepNone,
+    /// Multiplying by zero (`*0`).
+    ZeroMultiplier,
+    /// The sides, multiplier or bonus are so large the roll could overflow.
+    TooLarge,
+    /// Left over after the expression, e.g. a multiplier after the bonus.
+    Unexpected(String),
+}
+
+impl Display for DiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiceError::Empty => write!(f, "empty dice expression"),
+            DiceError::MissingD(s) => write!(f, "expected dice like 2d6, found `{s}`"),
+            DiceError::InvalidNumber(s) if s.is_empty() => write!(f, "expected a number at the end"),
+            DiceError::InvalidNumber(s) => write!(f, "expected a number, found `{s}`"),
+            DiceError::NoDice => write!(f, "dice need at least one die and one side"),
+            DiceError::TooManyDice(count) => write!(f, "can't roll {count} dice (at most {MAX_DICE})"),
+            DiceError::KeepTooMany { keep, count } => write!(f, "can't keep {keep} of {count} dice"),
+            DiceError::KeepNone => write!(f, "can't keep none of the dice"),
+            DiceError::ZeroMultiplier => write!(f, "can't multiply dice by zero"),
+            DiceError::TooLarge => write!(f, "dice are too large to roll"),
+            DiceError::Unexpected(s) => write!(f, "unexpected `{s}` (expected e.g. 3d6, 1d20kh1, 2d4*3 or 1d6+2)"),
+        }
+    }
+}
+
+impl std::error::Error for DiceError {}
+
+impl Dice {
+    pub const fn new(count: u32, sides: u32, bonus: i32) -> Self {
+        Self { count, sides, keep: None, multiplier: 1, bonus }
+    }
+
+    /// The kept dice times the multiplier, without the bonus. Parsed dice can't
+    /// overflow, see [DiceError::TooLarge].
+    pub fn roll_dice(&self, rng: &mut PRng) -> i32 {
+        let sides = i32::try_from(self.sides).unwrap_or(i32::MAX);
+        let rolls = (0..self.count).map(|_| rng.gen_range(1..=sides));
+        let sum: i32 = match self.keep {
+            None => rolls.sum(),
+            Some(keep) => {
+                let mut rolls = rolls.collect::<Vec<_>>();
+                let n = match keep {
+                    Keep::Highest(n) => { rolls.sort_unstable_by(|a, b| b.cmp(a)); n }
+                    Keep::Lowest(n) => { rolls.sort_unstable(); n }
+                };
+                rolls.iter().take(n as usize).sum()
+            },
+        };
+        sum.saturating_mul(i32::try_from(self.multiplier).unwrap_or(i32::MAX))
+    }
+
+    pub fn roll(&self, rng: &mut PRng) -> i32 {
+        self.roll_dice(rng) + self.bonus
+    }
+}
+
+impl Default for Dice {
+    fn default() -> Self {
+        Self::new(1, 3, 0)
+    }
+}
+
+impl Display for Dice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{n}")?,
+            Some(Keep::Lowest(n)) => write!(f, "kl{n}")?,
+            None => (),
+        }
+        if self.multiplier != 1 {
+            write!(f, "*{}", self.multiplier)?;
+        }
+        match self.bonus {
+            0 => Ok(()),
+            b if b > 0 => write!(f, "+{b}"),
+            b => write!(f, "{b}"),
+        }
+    }
+}
+
+/// Splits the number at the start of `s` off the rest.
+fn number(s: &str) -> Result<(u32, &str), DiceError> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let n = s[..end].parse().map_err(|_| DiceError::InvalidNumber(s.to_string()))?;
+    Ok((n, &s[end..]))
+}
+
+impl FromStr for Dice {
+    type Err = DiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.split_whitespace().collect::<String>().to_ascii_lowercase();
+        if s.is_empty() {
+            return Err(DiceError::Empty);
+        }
+
+        let (count, rest) = s.split_once('d').ok_or_else(|| DiceError::MissingD(s.clone()))?;
+        let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| DiceError::InvalidNumber(count.to_string()))? };
+        let (sides, mut rest) = number(rest)?;
+        let mut dice = Dice::new(count, sides, 0);
+
+        if count == 0 || sides == 0 {
+            return Err(DiceError::NoDice);
+        }
+        if count > MAX_DICE {
+            return Err(DiceError::TooManyDice(count));
+        }
+
+        let keep = [("kh", Keep::Highest as fn(u32) -> Keep), ("kl", Keep::Lowest)].into_iter()
+            .find_map(|(prefix, keep)| rest.strip_prefix(prefix).map(|r| (r, keep)));
+        if let Some((r, keep)) = keep {
+            let (n, r) = number(r)?;
+            if n == 0 {
+                return Err(DiceError::KeepNone);
+            }
+            if n > count {
+                return Err(DiceError::KeepTooMany { keep: n, count });
+            }
+            dice.keep = Some(keep(n));
+            rest = r;
+        }
+
+        if let Some(r) = rest.strip_prefix('*') {
+            (dice.multiplier, rest) = number(r)?;
+            if dice.multiplier == 0 {
+                return Err(DiceError::ZeroMultiplier);
+            }
+        }
+
+        if let Some(sign) = rest.chars().next().filter(|c| *c == '+' || *c == '-') {
+            let (bonus, r) = number(&rest[1..])?;
+            let bonus = i32::try_from(bonus).map_err(|_| DiceError::TooLarge)?;
+            dice.bonus = if sign == '-' { -bonus } else { bonus };
+            rest = r;
+        }
+
+        if !rest.is_empty() {
+            return Err(DiceError::Unexpected(rest.to_string()));
+        }
+
+        // The highest roll, all dice showing their highest side, has to fit.
+        let kept = match dice.keep {
+            Some(Keep::Highest(n) | Keep::Lowest(n)) => n,
+            None => count,
+        };
+        let highest = kept as i64 * sides as i64 * dice.multiplier as i64 + dice.bonus.max(0) as i64;
+        if highest > i32::MAX as i64 {
+            return Err(DiceError::TooLarge);
+        }
+        Ok(dice)
+    }
+}
+
+impl TryFrom<String> for Dice {
+    type Error = DiceError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::PRngBuilder;
+
+    fn dice(s: &str) -> Dice {
+        s.parse().unwrap_or_else(|e| panic!("{s}: {e}"))
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(dice("3d6+2"), Dice::new(3, 6, 2));
+        assert_eq!(dice("1d6-1"), Dice::new(1, 6, -1));
+        assert_eq!(dice("d20"), Dice::new(1, 20, 0));
+        assert_eq!(dice(" 2 D 8 "), Dice::new(2, 8, 0));
+        assert_eq!(dice("1d20kh1"), Dice { keep: Some(Keep::Highest(1)), ..Dice::new(1, 20, 0) });
+        assert_eq!(dice("4d6kl3"), Dice { keep: Some(Keep::Lowest(3)), ..Dice::new(4, 6, 0) });
+        assert_eq!(dice("2d4*3"), Dice { multiplier: 3, ..Dice::new(2, 4, 0) });
+        assert_eq!(
+            dice("4d6kh3*2+1"),
+            Dice { keep: Some(Keep::Highest(3)), multiplier: 2, ..Dice::new(4, 6, 1) },
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for s in ["3d6+2", "1d6-1", "1d20", "1d20kh1", "4d6kl3", "2d4*3", "4d6kh3*2+1"] {
+            assert_eq!(dice(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s: &str| s.parse::<Dice>().unwrap_err();
+        assert_eq!(error(" "), DiceError::Empty);
+        assert_eq!(error("6"), DiceError::MissingD("6".to_string()));
+        assert_eq!(error("xd6"), DiceError::InvalidNumber("x".to_string()));
+        assert_eq!(error("2d"), DiceError::InvalidNumber(String::new()));
+        assert_eq!(error("2d99999999999"), DiceError::InvalidNumber("99999999999".to_string()));
+        assert_eq!(error("0d6"), DiceError::NoDice);
+        assert_eq!(error("2d0"), DiceError::NoDice);
+        assert_eq!(error("101d6"), DiceError::TooManyDice(101));
+        assert_eq!(error("2d6kh3"), DiceError::KeepTooMany { keep: 3, count: 2 });
+        assert_eq!(error("2d6kh0"), DiceError::KeepNone);
+        assert_eq!(error("2d6kl0"), DiceError::KeepNone);
+        assert_eq!(error("2d6*0"), DiceError::ZeroMultiplier);
+        assert_eq!(error("1d6+1*2"), DiceError::Unexpected("*2".to_string()));
+        assert_eq!(error("1d6x"), DiceError::Unexpected("x".to_string()));
+    }
+
+    #[test]
+    fn too_large() {
+        let error = |s: &str| s.parse::<Dice>().unwrap_err();
+        assert_eq!(error("1d4294967295"), DiceError::TooLarge);
+        assert_eq!(error("1d6*4294967295"), DiceError::TooLarge);
+        assert_eq!(error("1d6+4294967295"), DiceError::TooLarge);
+        assert_eq!(error("1d6-2147483648"), DiceError::TooLarge);
+        assert_eq!(error("100d100000*1000"), DiceError::TooLarge);
+        // Only the kept dice count.
+        assert_eq!(error("2d1073741824"), DiceError::TooLarge);
+        assert_eq!(dice("2d1073741824kh1"), Dice { keep: Some(Keep::Highest(1)), ..Dice::new(2, 1 << 30, 0) });
+        assert_eq!(dice("1d2147483647").sides, i32::MAX as u32);
+        assert_eq!(dice("1d1+2147483646").bonus, i32::MAX - 1);
+    }
+
+    #[test]
+    fn roll() {
+        let mut rng = PRngBuilder::new_seeded(5).build();
+        let kept = dice("4d6kh3*2+1");
+        for _ in 0..200 {
+            let total = kept.roll(&mut rng);
+            assert!((7..=37).contains(&total) && total % 2 == 1, "{total}");
+        }
+        // Large dice that were made by hand don't panic.
+        Dice::new(1, u32::MAX, 0).roll(&mut rng);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::BTreeMap, hash::Hasher};
+
+use bevy::prelude::Resource;
+use rand::{SeedableRng, Rng, distributions::{uniform::{SampleUniform, SampleRange}, Standard}, prelude::Distribution};
+use rand_xoshiro::Xoroshiro128PlusPlus;
+use serde::{Deserialize, Serialize};
+use wyhash::WyHash;
+
+use crate::config;
+
+pub use self::dice::Dice;
+
+pub mod dice;
+
+/// Pseudo random number generator. Wrapper struct around Xoroshiro128++.
+/// (Newtype pattern: https://rust-unofficial.github.io/patterns/patterns/behavioural/newtype.html)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PRng(Xoroshiro128PlusPlus);
+
+impl PRng {
+
+    pub fn gen_range<T, R>(&mut self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.0.gen_range::<T, R>(range)
+    }
+
+    pub fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.0.gen_ratio(numerator, denominator)
+    }
+
+    pub fn gen<T>(&mut self) -> T
+    where 
+        Standard: Distribution<T>,
+    {
+        // same as self.0.gen()
+        Standard.sample(&mut self.0)
+    }
+
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        self.0.gen_bool(p)
+    }
+
+    /// A random element of `items`, or `None` if it's empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(&items[self.gen_range(0..items.len())])
+    }
+
+    /// `amount` distinct random elements of `items` (all of them if there are fewer),
+    /// in random order.
+    #[allow(dead_code)]
+    pub fn choose_multiple<'a, T>(&mut self, items: &'a [T], amount: usize) -> Vec<&'a T> {
+        let mut indices = (0..items.len()).collect::<Vec<_>>();
+        let amount = amount.min(items.len());
+        // A Fisher-Yates shuffle that stops after the first `amount` elements.
+        for i in 0..amount {
+            let j = self.gen_range(i..indices.len());
+            indices.swap(i, j);
+        }
+        indices[..amount].iter().map(|&i| &items[i]).collect()
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates).
+    #[allow(dead_code)]
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.gen_range(0..=i);
+            items.swap(i, j);
+        }
+    }
+
+    /// Index of a random entry of `weights`, each picked with a chance proportional
+    /// to its weight. `None` if all weights are zero.
+    pub fn weighted_index(&mut self, weights: impl IntoIterator<Item = u32> + Clone) -> Option<usize> {
+        let total: u32 = weights.clone().into_iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = self.gen_range(0..total);
+        for (i, weight) in weights.into_iter().enumerate() {
+            if roll < weight {
+                return Some(i);
+            }
+            roll -= weight;
+        }
+        unreachable!()
+    }
+
+    /// A random element of `items`, weighted by `weight`. For tables of
+    /// `(value, weight)` pairs use e.g. `|(_, w)| *w`. `None` if all weights are zero.
+    pub fn choose_weighted<'a, T>(&mut self, items: &'a [T], weight: impl Fn(&T) -> u32) -> Option<&'a T> {
+        self.weighted_index(items.iter().map(&weight)).map(|i| &items[i])
+    }
+
+    /// A normally distributed number (Box-Muller transform).
+    #[allow(dead_code)]
+    pub fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+        // 1 - [0, 1) keeps the logarithm finite.
+        let u1 = 1. - self.gen::<f64>();
+        let u2 = self.gen::<f64>();
+        let z = (-2. * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        mean + std_dev * z
+    }
+
+    /// A number between `min` and `max` from a triangular distribution peaking at `mode`.
+    #[allow(dead_code)]
+    pub fn triangular(&mut self, min: f64, max: f64, mode: f64) -> f64 {
+        debug_assert!(min <= mode && mode <= max, "triangular: mode {mode} not in [{min}, {max}]");
+        if max <= min {
+            return min;
+        }
+
+        let u = self.gen::<f64>();
+        let split = (mode - min) / (max - min);
+        if u < split {
+            min + (u * (max - min) * (mode - min)).sqrt()
+        } else {
+            max - ((1. - u) * (max - min) * (max - mode)).sqrt()
+        }
+    }
+}
+
+pub struct PRngBuilder {
+    hasher: WyHash, 
+}
+
+impl PRngBuilder {
+    /// Initializes a new builder with the specified seed.
+    pub fn new_seeded(seed: u64) -> Self {
+        Self { hasher: WyHash::with_seed(seed) }
+    }
+
+    /// Initialize a new builder with a randomly generated seed (thread-local).
+    pub fn new() -> Self {
+        Self { hasher: WyHash::with_seed(rand::random::<u64>()) }
+    }
+
+    pub fn write_i32(mut self, val: i32) -> Self {
+        self.hasher.write_i32(val);
+        self
+    }
+
+    pub fn write_u32(mut self, val: u32) -> Self {
+        self.hasher.write_u32(val);
+        self
+    }
+
+    pub fn write_u64(mut self, val: u64) -> Self {
+        self.hasher.write_u64(val);
+        self
+    }
+
+    pub fn build(self) -> PRng {
+        PRng(Xoroshiro128PlusPlus::seed_from_u64(self.hasher.finish()))
+    }
+}
+
+/// The subsystems that draw random numbers. Each gets its own stream, so e.g. an
+/// extra attack roll doesn't change what the next monster drops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RngStream {
+    MapGen,
+    Theme,
+    Spawning,
+    Combat,
+    Ai,
+    Loot,
+}
+
+impl RngStream {
+    fn seed(self) -> u64 {
+        match self {
+            RngStream::MapGen => config::MAP_GENERATION_SEED,
+            RngStream::Theme => config::THEME_SEED,
+            RngStream::Spawning => config::ENTITY_GENERATION_SEED,
+            RngStream::Combat => config::MELEE_ATTACK_SEED,
+            RngStream::Ai => config::AI_SEED,
+            RngStream::Loot => config::LOOT_SEED,
+        }
+    }
+
+    /// A builder for the stream on the level at `depth` of the game with `game_seed`.
+    /// Code that runs without [Rngs] (like the headless map generation) uses this
+    /// directly.
+    pub fn builder(self, game_seed: u64, depth: u32) -> PRngBuilder {
+        PRngBuilder::new_seeded(self.seed())
+            .write_u32(depth)
+            .write_u64(game_seed)
+    }
+}
+
+/// Bevy [Resource] with a [PRng] per [RngStream] for the current level, derived
+/// from the game seed and the depth. A stream is created the first time it's used
+/// and then keeps its state, which is saved along with the game (see
+/// [saveload](crate::saveload)) so that a reloaded game rolls exactly the same numbers.
+///
+/// For rolls that should only depend on when they happen (and not on how many
+/// numbers were drawn before), use [Rngs::for_turn] instead.
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rngs {
+    game_seed: u64,
+    depth: u32,
+    streams: BTreeMap<RngStream, PRng>,
+}
+
+impl Rngs {
+    pub fn new(game_seed: u64, depth: u32) -> Self {
+        Self { game_seed, depth, streams: BTreeMap::new() }
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut PRng {
+        let (game_seed, depth) = (self.game_seed, self.depth);
+        self.streams.entry(stream).or_insert_with(|| stream.builder(game_seed, depth).build())
+    }
+
+    /// A builder for a one-off [PRng] of `stream` at `turn`, independent of the state
+    /// of the stream. Add whatever else the rolls depend on before building it.
+    pub fn for_turn(&self, stream: RngStream, turn: u64) -> PRngBuilder {
+        stream.builder(self.game_seed, self.depth).write_u64(turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rng(seed: u64) -> PRng {
+        PRngBuilder::new_seeded(seed).build()
+    }
+
+    #[test]
+    fn shuffle() {
+        let mut items = (0..20).collect::<Vec<_>>();
+        rng(1).shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+
+        let mut again = (0..20).collect::<Vec<_>>();
+        rng(1).shuffle(&mut again);
+        assert_eq!(again, items);
+
+        rng(1).shuffle::<u32>(&mut []);
+    }
+
+    #[test]
+    fn choose_multiple() {
+        let items = (0..20).collect::<Vec<_>>();
+        let chosen = rng(2).choose_multiple(&items, 5);
+        assert_eq!(chosen.len(), 5);
+        let mut distinct = chosen.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 5);
+        assert_eq!(rng(2).choose_multiple(&items, 5), chosen);
+
+        assert_eq!(rng(2).choose_multiple(&items, 50).len(), 20);
+        assert!(rng(2).choose_multiple(&[] as &[u32], 3).is_empty());
+    }
+
+    #[test]
+    fn normal() {
+        let mut r = rng(3);
+        let samples = (0..10_000).map(|_| r.normal(10., 2.)).collect::<Vec<_>>();
+        assert!(samples.iter().all(|s| s.is_finite()));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!((mean - 10.).abs() < 0.1, "mean {mean}");
+        assert!((variance.sqrt() - 2.).abs() < 0.1, "standard deviation {}", variance.sqrt());
+
+        assert_eq!(rng(3).normal(10., 2.), samples[0]);
+    }
+
+    #[test]
+    fn triangular() {
+        let mut r = rng(4);
+        let samples = (0..10_000).map(|_| r.triangular(2., 8., 3.)).collect::<Vec<_>>();
+        assert!(samples.iter().all(|s| (2. ..=8.).contains(s)));
+        // The mean of a triangular distribution is (min + max + mode) / 3.
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 13. / 3.).abs() < 0.1, "mean {mean}");
+        assert!(samples.iter().filter(|s| **s < 3.).count() < samples.iter().filter(|s| **s > 3.).count());
+
+        assert_eq!(rng(4).triangular(2., 8., 3.), samples[0]);
+        assert_eq!(rng(4).triangular(5., 5., 5.), 5.);
+        assert!((0..100).map(|_| r.triangular(0., 1., 1.)).all(|s| (0. ..=1.).contains(&s)));
+    }
+}
@@ -46,6 +46,14 @@ impl Rect {
         )
     }
 
+    /// Return true if `p` lies within this [Rect] (edges included).
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= self.x1 as i32
+            && p.x <= self.x2 as i32
+            && p.y >= self.y1 as i32
+            && p.y <= self.y2 as i32
+    }
+
     pub fn iter_xy(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
         (self.y1..=self.y2)
             .flat_map(move |y| std::iter::repeat(y).zip(self.x1..=self.x2))
@@ -5,7 +5,11 @@ use bevy::prelude::States;
 pub enum MainState {
     #[default]
     LoadAssets,
+    /// The map is being built in the background, see [MapGenPlugin](crate::mapgen::MapGenPlugin).
+    Generating,
     Game,
+    /// The player has died, see [GameOverSummary](crate::pieces::death::GameOverSummary).
+    GameOver,
 }
 
 /// We have two different states during gameplay: Waiting for 
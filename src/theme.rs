@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{board::components::Tile, mapgen, random::RngStream};
+
+const THEMES: &str = include_str!("../assets/data/themes.ron");
+
+/// How a tile is drawn: an index into the ascii sprite sheet and a colour.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct TileLook {
+    pub glyph: usize,
+    pub color: (f32, f32, f32),
+}
+
+impl TileLook {
+    pub fn color(&self) -> Color {
+        Color::rgb(self.color.0, self.color.1, self.color.2)
+    }
+}
+
+/// A decoration drawn on some of the floor tiles, see [Theme::features].
+#[derive(Deserialize, Debug, Clone)]
+pub struct Feature {
+    pub name: String,
+    pub glyph: usize,
+    pub color: (f32, f32, f32),
+    /// Chance for each floor tile to get this feature.
+    pub chance: f64,
+}
+
+/// The look and contents of a level, see `assets/data/themes.ron`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    /// One of [BUILDER_NAMES](mapgen::BUILDER_NAMES).
+    pub builder: String,
+    /// Key into [SpawnTables](crate::pieces::spawn_table::SpawnTables).
+    pub spawn_table: String,
+    pub weight: u32,
+    pub min_depth: u32,
+    pub max_depth: u32,
+    pub floor: TileLook,
+    pub wall: TileLook,
+    pub door_open: TileLook,
+    pub door_closed: TileLook,
+    pub down_stairs: TileLook,
+    pub features: Vec<Feature>,
+}
+
+impl Theme {
+    pub fn look(&self, tile: Tile) -> TileLook {
+        match tile {
+            Tile::Floor => self.floor,
+            Tile::Wall => self.wall,
+            Tile::DoorOpen => self.door_open,
+            Tile::DoorClosed => self.door_closed,
+            Tile::DownStairs => self.down_stairs,
+        }
+    }
+
+    /// Like [Theme::look], but shows the feature with index `feature` on floor tiles.
+    pub fn decorated_look(&self, tile: Tile, feature: Option<usize>) -> TileLook {
+        match (tile, feature.and_then(|f| self.features.get(f))) {
+            (Tile::Floor, Some(f)) => TileLook { glyph: f.glyph, color: f.color },
+            _ => self.look(tile),
+        }
+    }
+}
+
+/// Bevy [Resource] with every [Theme] a level can have.
+#[derive(Resource, Debug, Clone)]
+pub struct Themes(pub Vec<Theme>);
+
+impl Default for Themes {
+    fn default() -> Self {
+        let themes: Vec<Theme> = ron::from_str(THEMES)
+            .unwrap_or_else(|e| panic!("invalid themes: {e}"));
+        assert!(!themes.is_empty(), "no themes defined");
+        for theme in themes.iter() {
+            assert!(
+                mapgen::BUILDER_NAMES.contains(&theme.builder.as_str()),
+                "theme {} uses unknown builder {}", theme.name, theme.builder
+            );
+        }
+        Self(themes)
+    }
+}
+
+impl Themes {
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.0.iter().find(|t| t.name == name)
+    }
+
+    /// Picks the theme of the level at `depth`, weighted by [Theme::weight]. Falls
+    /// back to the first theme if none allow that depth.
+    pub fn pick(&self, seed: u64, depth: u32) -> &Theme {
+        let mut rng = RngStream::Theme.builder(seed, depth).build();
+
+        let eligible = self.0.iter()
+            .filter(|t| t.weight > 0 && (t.min_depth..=t.max_depth).contains(&depth))
+            .collect::<Vec<_>>();
+        rng.choose_weighted(&eligible, |t| t.weight).copied().unwrap_or(&self.0[0])
+    }
+}
+
+/// Bevy [Resource] holding the [Theme] of the current level.
+#[derive(Resource, Debug, Clone)]
+pub struct ActiveTheme(pub Theme);